@@ -0,0 +1,91 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::config::config_dir;
+
+/// A saved remote folder. The `file_id` is authoritative; `path` is only the
+/// last known location and is refreshed whenever the bookmark is used.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub file_id: String,
+    pub path: String,
+}
+
+/// Bookmarks persisted as JSON under the config directory.
+/// 书签存储：以 JSON 形式保存在配置目录下，重启后仍然可用。
+pub struct BookmarkStore {
+    /// `None` when the store could not be loaded; changes then stay in memory
+    /// so a damaged `bookmarks.json` is never overwritten.
+    file: Option<PathBuf>,
+    entries: BTreeMap<String, Bookmark>,
+}
+
+impl BookmarkStore {
+    /// Load bookmarks from disk (an absent file means no bookmarks yet).
+    /// 从磁盘加载书签（文件不存在时视为空）。
+    pub fn load() -> Result<Self> {
+        let file = config_dir()?.join("bookmarks.json");
+        let entries = if file.exists() {
+            let text = std::fs::read_to_string(&file)?;
+            serde_json::from_str(&text)?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self {
+            file: Some(file),
+            entries,
+        })
+    }
+
+    /// Load bookmarks, falling back to an empty, unsaved store with a warning
+    /// when the file is corrupt or the config directory is unusable.
+    /// 加载书签；文件损坏或配置目录不可用时给出警告，并使用不写盘的空书签。
+    pub fn load_or_empty() -> Self {
+        Self::load().unwrap_or_else(|err| {
+            eprintln!(
+                "⚠️ Could not load bookmarks: {}; starting without them (changes will not be saved)",
+                err
+            );
+            Self {
+                file: None,
+                entries: BTreeMap::new(),
+            }
+        })
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        let text = serde_json::to_string_pretty(&self.entries)?;
+        std::fs::write(file, text)?;
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Bookmark> {
+        self.entries.get(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Bookmark)> {
+        self.entries.iter()
+    }
+
+    /// Insert or replace a bookmark and persist the store.
+    /// 新增或覆盖书签并立即写盘。
+    pub fn insert(&mut self, name: &str, bookmark: Bookmark) -> Result<()> {
+        self.entries.insert(name.to_string(), bookmark);
+        self.save()
+    }
+
+    /// Remove a bookmark, returning whether it existed.
+    /// 删除书签，返回是否存在。
+    pub fn remove(&mut self, name: &str) -> Result<bool> {
+        let existed = self.entries.remove(name).is_some();
+        if existed {
+            self.save()?;
+        }
+        Ok(existed)
+    }
+}
@@ -0,0 +1,287 @@
+use crate::remote::filter::CATEGORIES;
+use crate::remote::output::Column;
+
+/// What a word on the command line refers to, used for completion.
+/// 命令行参数的类别，用于决定补全方式。
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArgKind {
+    /// Free-form text; nothing to complete.
+    Text,
+    /// Remote file or folder.
+    RemotePath,
+    /// Remote folder only.
+    RemoteDir,
+    /// Local file or directory.
+    LocalPath,
+    /// One of a fixed set of values.
+    Values(&'static [&'static str]),
+    /// Name of a saved bookmark.
+    Bookmark,
+}
+
+pub struct OptionSpec {
+    pub names: &'static [&'static str],
+    /// Kind of the value the option consumes, if it takes one.
+    pub value: Option<ArgKind>,
+}
+
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub options: &'static [OptionSpec],
+    /// Kinds of the positional arguments; the last entry repeats.
+    pub args: &'static [ArgKind],
+}
+
+impl CommandSpec {
+    pub fn option(&self, name: &str) -> Option<&OptionSpec> {
+        self.options.iter().find(|o| o.names.contains(&name))
+    }
+
+    /// Kind of the positional argument at `index` (0-based).
+    pub fn arg(&self, index: usize) -> ArgKind {
+        self.args
+            .get(index)
+            .or(self.args.last())
+            .copied()
+            .unwrap_or(ArgKind::Text)
+    }
+}
+
+const LS_ORDER_BY: &[&str] = &["created_at", "updated_at", "name", "size", "name_enhanced"];
+const SEARCH_ORDER_BY: &[&str] = &["created_at", "updated_at", "name", "size"];
+const ORDER_DIRECTION: &[&str] = &["ASC", "DESC"];
+
+const fn flag(names: &'static [&'static str]) -> OptionSpec {
+    OptionSpec { names, value: None }
+}
+
+const fn valued(names: &'static [&'static str], kind: ArgKind) -> OptionSpec {
+    OptionSpec {
+        names,
+        value: Some(kind),
+    }
+}
+
+/// Every shell command with its options and positional arguments.
+/// 所有命令及其选项、位置参数的定义。
+pub const COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "help",
+        options: &[],
+        args: &[],
+    },
+    CommandSpec {
+        name: "login",
+        options: &[],
+        args: &[],
+    },
+    CommandSpec {
+        name: "ls",
+        options: &[
+            valued(&["--limit"], ArgKind::Text),
+            valued(&["--marker"], ArgKind::Text),
+            valued(&["--order-by"], ArgKind::Values(LS_ORDER_BY)),
+            valued(&["--order-direction"], ArgKind::Values(ORDER_DIRECTION)),
+            flag(&["--all", "-a"]),
+            flag(&["--long", "-l"]),
+            flag(&["--recursive", "-R"]),
+            valued(&["--type"], ArgKind::Values(&["file", "folder"])),
+            valued(&["--category"], ArgKind::Values(CATEGORIES)),
+            valued(&["--ext"], ArgKind::Text),
+            valued(&["--min-size", "--max-size"], ArgKind::Text),
+            valued(&["--newer", "--older"], ArgKind::Text),
+            flag(&["--human-readable", "-h"]),
+            valued(&["--columns"], ArgKind::Values(Column::NAMES)),
+            flag(&["--json"]),
+            flag(&["--jsonl"]),
+            flag(&["--csv"]),
+            flag(&["--no-pager"]),
+        ],
+        args: &[ArgKind::RemotePath],
+    },
+    CommandSpec {
+        name: "cd",
+        options: &[],
+        args: &[ArgKind::RemoteDir],
+    },
+    CommandSpec {
+        name: "pushd",
+        options: &[],
+        args: &[ArgKind::RemoteDir],
+    },
+    CommandSpec {
+        name: "popd",
+        options: &[],
+        args: &[],
+    },
+    CommandSpec {
+        name: "dirs",
+        options: &[],
+        args: &[],
+    },
+    CommandSpec {
+        name: "bookmark",
+        options: &[],
+        args: &[
+            ArgKind::Values(&["add", "rm", "ls", "go"]),
+            ArgKind::Bookmark,
+            ArgKind::RemoteDir,
+        ],
+    },
+    CommandSpec {
+        name: "pwd",
+        options: &[],
+        args: &[],
+    },
+    CommandSpec {
+        name: "mkdir",
+        options: &[],
+        args: &[ArgKind::RemoteDir],
+    },
+    CommandSpec {
+        name: "search",
+        options: &[
+            valued(&["--limit"], ArgKind::Text),
+            valued(&["--marker"], ArgKind::Text),
+            valued(&["--order-by"], ArgKind::Values(SEARCH_ORDER_BY)),
+            valued(&["--order-direction"], ArgKind::Values(ORDER_DIRECTION)),
+            flag(&["--return-total"]),
+            flag(&["--all", "-a"]),
+            flag(&["--global"]),
+            valued(&["--in", "--path"], ArgKind::RemoteDir),
+            valued(&["--query"], ArgKind::Text),
+            flag(&["--json"]),
+            flag(&["--jsonl"]),
+            flag(&["--csv"]),
+            flag(&["--no-pager"]),
+        ],
+        args: &[ArgKind::Text],
+    },
+    CommandSpec {
+        name: "tree",
+        options: &[
+            valued(&["-L"], ArgKind::Text),
+            flag(&["-d"]),
+            flag(&["--no-pager"]),
+        ],
+        args: &[ArgKind::RemoteDir],
+    },
+    CommandSpec {
+        name: "stat",
+        options: &[flag(&["--json"])],
+        args: &[ArgKind::RemotePath],
+    },
+    CommandSpec {
+        name: "info",
+        options: &[flag(&["--json"])],
+        args: &[ArgKind::RemotePath],
+    },
+    CommandSpec {
+        name: "du",
+        options: &[flag(&["-s"]), valued(&["-d"], ArgKind::Text), flag(&["-h"])],
+        args: &[ArgKind::RemoteDir],
+    },
+    CommandSpec {
+        name: "df",
+        options: &[],
+        args: &[],
+    },
+    CommandSpec {
+        name: "set",
+        options: &[],
+        args: &[ArgKind::Values(&["bandwidth"]), ArgKind::Text],
+    },
+    CommandSpec {
+        name: "put",
+        options: &[valued(&["--limit-rate"], ArgKind::Text)],
+        args: &[ArgKind::LocalPath],
+    },
+    CommandSpec {
+        name: "get",
+        options: &[
+            flag(&["-r", "--recursive"]),
+            flag(&["--no-verify"]),
+            flag(&["--resume"]),
+            flag(&["--force"]),
+            flag(&["--skip-existing"]),
+            flag(&["--skip-if-same"]),
+            flag(&["--rename"]),
+            valued(&["--connections"], ArgKind::Values(&["auto"])),
+            valued(&["--chunk-size"], ArgKind::Text),
+            valued(&["--limit-rate"], ArgKind::Text),
+        ],
+        args: &[ArgKind::RemotePath, ArgKind::LocalPath],
+    },
+    CommandSpec {
+        name: "cat",
+        options: &[valued(&["--range"], ArgKind::Text)],
+        args: &[ArgKind::RemotePath],
+    },
+    CommandSpec {
+        name: "head",
+        options: &[
+            valued(&["-n"], ArgKind::Text),
+            valued(&["-c"], ArgKind::Text),
+        ],
+        args: &[ArgKind::RemotePath],
+    },
+    CommandSpec {
+        name: "tail",
+        options: &[
+            valued(&["-n"], ArgKind::Text),
+            valued(&["-c"], ArgKind::Text),
+        ],
+        args: &[ArgKind::RemotePath],
+    },
+    CommandSpec {
+        name: "cp",
+        options: &[],
+        args: &[ArgKind::RemotePath],
+    },
+    CommandSpec {
+        name: "mv",
+        options: &[],
+        args: &[ArgKind::RemotePath],
+    },
+    CommandSpec {
+        name: "rm",
+        options: &[],
+        args: &[ArgKind::RemotePath],
+    },
+    CommandSpec {
+        name: "lls",
+        options: &[],
+        args: &[ArgKind::LocalPath],
+    },
+    CommandSpec {
+        name: "lcd",
+        options: &[],
+        args: &[ArgKind::LocalPath],
+    },
+    CommandSpec {
+        name: "lpwd",
+        options: &[],
+        args: &[],
+    },
+    CommandSpec {
+        name: "exit",
+        options: &[],
+        args: &[],
+    },
+    CommandSpec {
+        name: "quit",
+        options: &[],
+        args: &[],
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static CommandSpec> {
+    COMMANDS.iter().find(|c| c.name == name)
+}
+
+/// Whether `opt` of `cmd` consumes the following word as its value.
+pub fn option_takes_value(cmd: &str, opt: &str) -> bool {
+    find(cmd)
+        .and_then(|spec| spec.option(opt))
+        .is_some_and(|o| o.value.is_some())
+}
@@ -0,0 +1,335 @@
+use anyhow::Result;
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+use std::fs;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+use tokio::runtime::Handle;
+
+use crate::bookmark::BookmarkStore;
+use crate::commands::{self, ArgKind};
+use crate::expand_local_path;
+use crate::login::check_login;
+use crate::remote::{
+    cache::ListingCache,
+    drive::get_drive_id,
+    glob,
+    ls::{get_subfolder_id, FileItem},
+};
+
+/// How long TAB waits for a listing that is not cached yet. On timeout the
+/// fetch keeps running in the background and fills the cache for the next TAB.
+const COMPLETION_WAIT: Duration = Duration::from_millis(500);
+
+#[derive(Clone)]
+pub struct AliyunCompleter {
+    remote_stack: Arc<Mutex<Vec<(String, String)>>>,
+    drive_id: Arc<Mutex<Option<String>>>,
+    cache: ListingCache,
+    runtime: Handle,
+}
+
+impl AliyunCompleter {
+    /// Share the shell's folder stack, drive id and listing cache. Must be
+    /// called from within the shell's Tokio runtime.
+    pub fn new(
+        remote_stack: Arc<Mutex<Vec<(String, String)>>>,
+        drive_id: Arc<Mutex<Option<String>>>,
+        cache: ListingCache,
+    ) -> Self {
+        Self {
+            remote_stack,
+            drive_id,
+            cache,
+            runtime: Handle::current(),
+        }
+    }
+
+    /// List the remote folder named by `dir_part` (relative to the shell's
+    /// current folder, absolute, or using `..`).
+    /// 列出 `dir_part` 指向的远程目录（支持相对、绝对路径与 `..`）。
+    fn remote_entries(&self, dir_part: &str) -> Option<Arc<Vec<FileItem>>> {
+        let token = check_login().ok()?;
+        let stack = self.remote_stack.lock().unwrap().clone();
+        let cache = self.cache.clone();
+        let drive_id = Arc::clone(&self.drive_id);
+        let dir_part = dir_part.to_string();
+
+        // 在 shell 的运行时中解析（命中缓存时立即返回），补全线程只做有限等待
+        let (tx, rx) = mpsc::channel();
+        self.runtime.spawn(async move {
+            let result = fetch_remote_entries(&cache, &drive_id, &token, stack, &dir_part).await;
+            let _ = tx.send(result.ok());
+        });
+        rx.recv_timeout(COMPLETION_WAIT).ok().flatten()
+    }
+
+    /// Complete a (possibly nested) remote path prefix.
+    /// 补全远程路径（可包含多级目录）。
+    fn remote_pairs(&self, prefix: &str, include_dirs: bool, include_files: bool) -> Vec<Pair> {
+        let (dir_part, name_part) = match prefix.rfind('/') {
+            Some(idx) => (&prefix[..=idx], &prefix[idx + 1..]),
+            None => ("", prefix),
+        };
+        self.remote_entries(dir_part)
+            .map(|entries| {
+                remote_name_pairs(&entries, dir_part, name_part, include_dirs, include_files)
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Completer for AliyunCompleter {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let input = &line[..pos];
+        let words = glob::split_partial(input);
+        let (start, current) = words.last().cloned().unwrap_or((0, String::new()));
+
+        if words.len() <= 1 {
+            let names: Vec<&str> = commands::COMMANDS.iter().map(|c| c.name).collect();
+            return Ok((start, command_pairs(&current, &names)));
+        }
+
+        let Some(spec) = commands::find(&words[0].1) else {
+            return Ok((start, vec![]));
+        };
+        let current = current.as_str();
+        let previous = &words[..words.len() - 1];
+
+        // 上一个词是需要取值的选项：补全该选项的值
+        let pending_value = previous
+            .last()
+            .filter(|_| previous.len() > 1)
+            .and_then(|(_, word)| spec.option(word))
+            .and_then(|o| o.value);
+        let kind = if let Some(kind) = pending_value {
+            kind
+        } else if current.starts_with('-') {
+            let names: Vec<&str> = spec
+                .options
+                .iter()
+                .flat_map(|o| o.names.iter().copied())
+                .collect();
+            return Ok((start, command_pairs(current, &names)));
+        } else {
+            // 统计当前词之前的位置参数个数（跳过选项及其取值）
+            let mut index = 0;
+            let mut skip_value = false;
+            for (_, word) in &previous[1..] {
+                if skip_value {
+                    skip_value = false;
+                } else if word.len() > 1 && word.starts_with('-') {
+                    skip_value = spec.option(word).is_some_and(|o| o.value.is_some());
+                } else {
+                    index += 1;
+                }
+            }
+            spec.arg(index)
+        };
+
+        let pairs = match kind {
+            ArgKind::Text => vec![],
+            ArgKind::RemotePath => {
+                let mut result = self.remote_pairs(current, true, true);
+                result.extend(special_remote_targets(current));
+                result
+            }
+            ArgKind::RemoteDir => {
+                let mut result = self.remote_pairs(current, true, false);
+                result.extend(special_remote_targets(current));
+                result
+            }
+            ArgKind::LocalPath => collect_local_pairs(current),
+            ArgKind::Values(values) => value_pairs(current, values),
+            ArgKind::Bookmark => BookmarkStore::load()
+                .map(|store| {
+                    let names: Vec<&str> = store.iter().map(|(name, _)| name.as_str()).collect();
+                    value_pairs(current, &names)
+                })
+                .unwrap_or_default(),
+        };
+
+        Ok((start, pairs))
+    }
+}
+
+impl Helper for AliyunCompleter {}
+
+impl Hinter for AliyunCompleter {
+    type Hint = String;
+}
+
+impl Highlighter for AliyunCompleter {}
+
+impl Validator for AliyunCompleter {}
+
+/// Resolve `dir_part` against the given folder stack and list that folder
+/// for autocompletion, going through the shared listing cache.
+/// 根据目录栈解析 `dir_part`，经共享缓存获取该远程目录下的所有条目，用于命令自动补全。
+async fn fetch_remote_entries(
+    cache: &ListingCache,
+    drive_id: &Mutex<Option<String>>,
+    token: &str,
+    stack: Vec<(String, String)>,
+    dir_part: &str,
+) -> Result<Arc<Vec<FileItem>>> {
+    let cached_id = drive_id.lock().unwrap().clone();
+    let drive_id = match cached_id {
+        Some(id) => id,
+        None => {
+            let id = get_drive_id(token).await?;
+            *drive_id.lock().unwrap() = Some(id.clone());
+            id
+        }
+    };
+
+    let absolute = dir_part.starts_with('/');
+    let mut ids: Vec<String> = if absolute {
+        vec!["root".to_string()]
+    } else {
+        stack.into_iter().map(|(_, id)| id).collect()
+    };
+    for (i, comp) in dir_part.split('/').enumerate() {
+        if comp.is_empty() || comp == "." || (absolute && i == 1 && comp == "root") {
+            continue;
+        }
+        if comp == ".." {
+            if ids.len() > 1 {
+                ids.pop();
+            }
+            continue;
+        }
+        let current = ids.last().cloned().unwrap_or_else(|| "root".to_string());
+        let next = get_subfolder_id(cache, token, &drive_id, &current, comp)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Folder not found: {}", comp))?;
+        ids.push(next);
+    }
+
+    let folder_id = ids.last().cloned().unwrap_or_else(|| "root".to_string());
+    cache.items(token, &drive_id, &folder_id).await
+}
+
+fn command_pairs(prefix: &str, commands: &[&str]) -> Vec<Pair> {
+    commands
+        .iter()
+        .filter(|c| c.starts_with(prefix))
+        .map(|c| Pair {
+            display: c.to_string(),
+            replacement: c.to_string(),
+        })
+        .collect()
+}
+
+/// Enumerated values, matched case-insensitively.
+fn value_pairs(prefix: &str, values: &[&str]) -> Vec<Pair> {
+    let prefix = prefix.to_lowercase();
+    values
+        .iter()
+        .filter(|v| v.to_lowercase().starts_with(&prefix))
+        .map(|v| Pair {
+            display: v.to_string(),
+            replacement: glob::quote_word(v),
+        })
+        .collect()
+}
+
+fn remote_name_pairs(
+    entries: &[FileItem],
+    dir_part: &str,
+    prefix: &str,
+    include_dirs: bool,
+    include_files: bool,
+) -> Vec<Pair> {
+    let mut pairs: Vec<Pair> = entries
+        .iter()
+        .filter(|entry| {
+            let is_dir = entry.kind == "folder";
+            (include_dirs || !is_dir) && (include_files || is_dir) && entry.name.starts_with(prefix)
+        })
+        .map(|entry| {
+            let mut display = entry.name.clone();
+            if entry.kind == "folder" {
+                display.push('/');
+            }
+            Pair {
+                replacement: glob::quote_word(&format!("{}{}", dir_part, display)),
+                display,
+            }
+        })
+        .collect();
+    pairs.sort_by(|a, b| a.display.cmp(&b.display));
+    pairs
+}
+
+fn collect_local_pairs(prefix: &str) -> Vec<Pair> {
+    let mut prefix_owned = prefix.to_string();
+    if prefix_owned == "~" {
+        prefix_owned = "~/".to_string();
+    } else if prefix_owned == "$HOME" {
+        prefix_owned = "$HOME/".to_string();
+    }
+
+    let (dir_part, file_part) = if prefix_owned.ends_with('/') {
+        (prefix_owned.clone(), String::new())
+    } else if let Some(idx) = prefix_owned.rfind('/') {
+        (
+            prefix_owned[..idx + 1].to_string(),
+            prefix_owned[idx + 1..].to_string(),
+        )
+    } else {
+        (String::new(), prefix_owned.clone())
+    };
+
+    let mut dir_for_fs = if dir_part.is_empty() {
+        ".".to_string()
+    } else {
+        dir_part.trim_end_matches('/').to_string()
+    };
+    if dir_for_fs.is_empty() && dir_part.starts_with('/') {
+        dir_for_fs = "/".to_string();
+    }
+
+    let expanded_dir = expand_local_path(&dir_for_fs);
+    let mut pairs = Vec::new();
+    if let Ok(read_dir) = fs::read_dir(&expanded_dir) {
+        for entry in read_dir.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !file_part.is_empty() && !name.starts_with(&file_part) {
+                continue;
+            }
+            let mut path = format!("{}{}", dir_part, name);
+            if entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
+                path.push('/');
+            }
+            pairs.push(Pair {
+                replacement: glob::quote_word(&path),
+                display: path,
+            });
+        }
+    }
+    pairs.sort_by(|a, b| a.display.cmp(&b.display));
+    pairs
+}
+
+fn special_remote_targets(prefix: &str) -> Vec<Pair> {
+    let specials = ["..", "../", ".", "./"];
+    specials
+        .iter()
+        .filter(|s| s.starts_with(prefix))
+        .map(|s| Pair {
+            display: s.to_string(),
+            replacement: s.to_string(),
+        })
+        .collect()
+}
@@ -0,0 +1,82 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::path::PathBuf;
+
+/// Directory holding persistent ShellAliyun state (bookmarks, config).
+/// 返回 ShellAliyun 持久化数据（书签、配置）所在目录，不存在时自动创建。
+pub fn config_dir() -> Result<PathBuf> {
+    let dir = dirs::config_dir()
+        .ok_or_else(|| anyhow!("Cannot determine config directory"))?
+        .join("shellaliyun");
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// User settings read from `config.json` in the config directory. Every key
+/// is optional.
+/// 用户配置（配置目录下的 `config.json`），所有字段均可省略。
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Default columns for `ls -l`, e.g. `"type,size,updated,name"`.
+    pub ls_columns: Option<String>,
+    /// Listing colours in `LS_COLORS` syntax, e.g. `"di=01;34:video=35:*.iso=31"`.
+    pub colors: Option<String>,
+    /// Prefix listed names with an icon for their type.
+    pub classify: bool,
+    /// Default `get --connections`: a number or `"auto"`.
+    pub connections: Option<Value>,
+    /// Default `get --chunk-size`: bytes, or a size such as `"16M"`.
+    pub chunk_size: Option<Value>,
+    /// Time-of-day bandwidth caps shared by every transfer.
+    pub bandwidth_schedule: Vec<ScheduleEntry>,
+}
+
+/// One `bandwidth_schedule` entry, e.g.
+/// `{ "from": "09:00", "to": "18:00", "rate": "2M" }`.
+#[derive(Debug, Deserialize)]
+pub struct ScheduleEntry {
+    pub from: String,
+    pub to: String,
+    pub rate: Value,
+}
+
+/// A setting written either as a JSON number or as a string.
+/// 可写成数字或字符串的配置项。
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+    Number(u64),
+    Text(String),
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Text(text) => f.write_str(text),
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Self> {
+        let file = config_dir()?.join("config.json");
+        if !file.exists() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(&file)?;
+        serde_json::from_str(&text)
+            .with_context(|| format!("Invalid config file {}", file.display()))
+    }
+
+    /// Load the config, falling back to the defaults with a warning when the
+    /// file is invalid so a typo never keeps the shell from starting.
+    /// 加载配置；文件无效时给出警告并使用默认配置，避免因笔误无法启动。
+    pub fn load_or_default() -> Self {
+        Self::load().unwrap_or_else(|err| {
+            eprintln!("⚠️ {:#}; using default settings", err);
+            Self::default()
+        })
+    }
+}
@@ -16,7 +16,7 @@ fn generate_code_verifier_and_challenge() -> (String, String) {
         .map(char::from)
         .collect();
     let hash = Sha256::digest(code_verifier.as_bytes());
-    let code_challenge = general_purpose::URL_SAFE.encode(&hash);
+    let code_challenge = general_purpose::URL_SAFE.encode(hash);
     (code_verifier, code_challenge)
 }
 
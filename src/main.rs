@@ -1,24 +1,57 @@
 use anyhow::Result;
 use colored::Colorize;
-use rustyline::completion::{Completer, Pair};
 use rustyline::Editor;
-use rustyline::{Context, Helper};
-use shlex::Shlex;
-use std::{fs, path::PathBuf, sync::Arc};
-use tokio::sync::Mutex;
+use std::{
+    fs,
+    io::IsTerminal,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 
+mod bookmark;
+mod commands;
+mod completer;
+mod config;
 mod login;
+mod pager;
 mod remote;
 
+use bookmark::{Bookmark, BookmarkStore};
+use completer::AliyunCompleter;
+use config::Config;
 use login::{check_login, oauth_login};
 use remote::{
+    bandwidth::{parse_rate, ScheduleEntry},
+    cache::ListingCache,
+    cat::{cat, Portion},
+    concurrency::Connections,
     drive::get_drive_id,
-    ls::{get_subfolder_id, list_remote_files, ListOptions},
+    du::{print_disk_usage, DuOptions},
+    filter::{parse_size, parse_time, ItemFilter, CATEGORIES},
+    get::{download, plan_folder, DownloadJob, DownloadOptions, ExistingPolicy, MAX_CHUNK_SIZE},
+    glob,
+    ls::{
+        collect_records, get_subfolder_id, list_recursive, list_remote_files, FileItem,
+        ListOptions, NotFound,
+    },
+    output::{
+        human_size, print_items, print_listing, Column, ListFormat, Listing, Record,
+        DEFAULT_COLUMNS,
+    },
     search::{search_files, SearchOptions},
+    theme::Theme,
+    tree::{print_tree, TreeOptions},
+    walk::walk,
 };
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // 输出不是终端或设置了 NO_COLOR 时关闭颜色
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    if no_color || !std::io::stdout().is_terminal() {
+        colored::control::set_override(false);
+    }
+
     println!("{}", "AliyunDrive CLI".bold());
     println!("Type 'help' for commands.");
 
@@ -33,11 +66,16 @@ struct Shell {
     remote_cwd: String,
     remote_path: String,
     remote_stack: Vec<(String, String)>,
-    drive_id_cache: Option<String>,
-    completer_remote_cwd: Arc<Mutex<String>>,
+    prev_remote_stack: Option<Vec<(String, String)>>,
+    dir_stack: Vec<Vec<(String, String)>>,
+    bookmarks: BookmarkStore,
+    config: Config,
+    drive_id: Arc<Mutex<Option<String>>>,
+    cache: ListingCache,
+    completer_remote_stack: Arc<Mutex<Vec<(String, String)>>>,
 }
 
-fn expand_local_path(input: &str) -> PathBuf {
+pub(crate) fn expand_local_path(input: &str) -> PathBuf {
     if input.is_empty() {
         return PathBuf::from(input);
     }
@@ -59,23 +97,55 @@ fn expand_local_path(input: &str) -> PathBuf {
 
 impl Shell {
     fn new() -> Result<Self> {
-        let completer_remote_cwd = Arc::new(Mutex::new("root".to_string()));
-        let completer = AliyunCompleter::new(Arc::clone(&completer_remote_cwd));
+        let root_stack = vec![("/root".to_string(), "root".to_string())];
+        let completer_remote_stack = Arc::new(Mutex::new(root_stack.clone()));
+        let drive_id = Arc::new(Mutex::new(None));
+        let cache = ListingCache::new(reqwest::Client::new());
+        let completer = AliyunCompleter::new(
+            Arc::clone(&completer_remote_stack),
+            Arc::clone(&drive_id),
+            cache.clone(),
+        );
         let mut rl = Editor::<AliyunCompleter, _>::new()?;
         rl.set_helper(Some(completer));
+        let config = Config::load_or_default();
+        remote::theme::init(Theme::load(config.colors.as_deref(), config.classify));
+        let schedule = config
+            .bandwidth_schedule
+            .iter()
+            .filter_map(
+                |e| match ScheduleEntry::parse(&e.from, &e.to, &e.rate.to_string()) {
+                    Ok(entry) => Some(entry),
+                    Err(err) => {
+                        eprintln!(
+                            "⚠️ Skipping bandwidth schedule entry {}-{}: {}",
+                            e.from, e.to, err
+                        );
+                        None
+                    }
+                },
+            )
+            .collect();
+        remote::bandwidth::init(schedule);
 
         Ok(Self {
             rl,
             local_cwd: std::env::current_dir()?,
             remote_cwd: "root".to_string(),
             remote_path: "/root".to_string(),
-            remote_stack: vec![("/root".to_string(), "root".to_string())],
-            drive_id_cache: None,
-            completer_remote_cwd,
+            remote_stack: root_stack,
+            prev_remote_stack: None,
+            dir_stack: Vec::new(),
+            bookmarks: BookmarkStore::load_or_empty(),
+            config,
+            drive_id,
+            cache,
+            completer_remote_stack,
         })
     }
 
     async fn run(&mut self) -> Result<()> {
+        self.prefetch_listing(&self.remote_cwd);
         loop {
             let prompt = format!("{} ", format!("aliyun:{}> ", self.remote_path).blue());
             let line = self.rl.readline(&prompt);
@@ -104,12 +174,17 @@ impl Shell {
     }
 
     async fn dispatch(&mut self, line: &str) -> Result<()> {
-        let mut parts = Shlex::new(line).collect::<Vec<_>>();
-        if parts.is_empty() {
+        let mut words = glob::split_words(line)?;
+        if words.is_empty() {
             return Ok(());
         }
 
-        let cmd = parts.remove(0);
+        let cmd = words.remove(0).text;
+        let parts = if GLOB_COMMANDS.contains(&cmd.as_str()) {
+            self.expand_globs(&cmd, words).await?
+        } else {
+            words.into_iter().map(|w| w.text).collect()
+        };
         match cmd.as_str() {
             "help" => self.cmd_help(),
             "exit" | "quit" => {
@@ -123,26 +198,140 @@ impl Shell {
             "ls" => self.cmd_ls(parts).await?,
             "mkdir" => self.cmd_mkdir(parts).await?,
             "cd" => self.cmd_cd(parts).await?,
+            "pushd" => self.cmd_pushd(parts).await?,
+            "popd" => self.cmd_popd(parts).await?,
+            "dirs" => self.cmd_dirs(),
+            "bookmark" => self.cmd_bookmark(parts).await?,
             "lls" => self.cmd_lls(parts)?,
             "lcd" => self.cmd_lcd(parts)?,
             "lpwd" => println!("{}", self.local_cwd.display()),
             "put" => self.cmd_put(parts).await?,
             "get" => self.cmd_get(parts).await?,
+            "cat" | "head" | "tail" => self.cmd_cat(&cmd, parts).await?,
             "cp" => self.cmd_cp(parts).await?,
             "mv" => self.cmd_mv(parts).await?,
             "rm" => self.cmd_rm(parts).await?,
             "search" => self.cmd_search(parts).await?,
+            "tree" => self.cmd_tree(parts).await?,
+            "stat" | "info" => self.cmd_stat(parts).await?,
+            "du" => self.cmd_du(parts).await?,
+            "set" => self.cmd_set(parts)?,
+            "df" => {
+                let (token, _) = self.ensure_auth().await?;
+                remote::drive::print_disk_free(&token).await?;
+            }
             _ => println!("Unknown command: {}", cmd),
         }
         Ok(())
     }
 
+    /// Expand unquoted wildcards in the remote path arguments of `cmd`.
+    /// 展开命令中远程路径参数里未加引号的通配符。
+    async fn expand_globs(&mut self, cmd: &str, words: Vec<glob::Word>) -> Result<Vec<String>> {
+        let mut positional = Vec::new();
+        let mut skip_value = false;
+        for (i, word) in words.iter().enumerate() {
+            if skip_value {
+                skip_value = false;
+            } else if word.pattern.is_none() && word.text.len() > 1 && word.text.starts_with('-') {
+                skip_value = commands::option_takes_value(cmd, &word.text);
+            } else {
+                positional.push(i);
+            }
+        }
+        // 最后一个参数是目标（本地路径或远程目标目录），不参与展开
+        let has_target = matches!(cmd, "get" | "cp" | "mv") && positional.len() >= 2;
+        if has_target {
+            positional.pop();
+        }
+
+        let mut args = Vec::new();
+        let mut needs_target = false;
+        for (i, word) in words.into_iter().enumerate() {
+            match word.pattern {
+                Some(pattern) if positional.contains(&i) => {
+                    let (token, drive_id) = self.ensure_auth().await?;
+                    let matches = self.expand_remote_glob(&token, &drive_id, &pattern).await?;
+                    // `get *.txt` 没有目标参数：展开为多个文件时下载到当前本地目录
+                    if cmd == "get" && !has_target && matches.len() > 1 {
+                        needs_target = true;
+                    }
+                    args.extend(matches);
+                }
+                _ => args.push(word.text),
+            }
+        }
+        if needs_target {
+            args.push(".".to_string());
+        }
+        Ok(args)
+    }
+
+    /// Match one glob pattern against remote folder listings.
+    /// 将单个通配模式与远程目录列表进行匹配，无匹配时报错。
+    async fn expand_remote_glob(
+        &self,
+        token: &str,
+        drive_id: &str,
+        pattern: &str,
+    ) -> Result<Vec<String>> {
+        let mut results = Vec::new();
+        for alt in glob::expand_braces(pattern) {
+            let absolute = alt.starts_with('/');
+            let dir_only = alt.ends_with('/');
+            let comps: Vec<&str> = alt.split('/').filter(|c| !c.is_empty()).collect();
+            if comps.is_empty() {
+                continue;
+            }
+            // 第一个含通配符的片段之前的部分按普通路径解析（支持 `..` 与绝对路径）
+            let split = comps
+                .iter()
+                .position(|c| glob::has_magic(c))
+                .unwrap_or(comps.len() - 1);
+            let prefix = comps[..split]
+                .iter()
+                .map(|c| glob::unescape(c))
+                .collect::<Vec<_>>()
+                .join("/");
+            let prefix = if absolute {
+                format!("/{}", prefix)
+            } else {
+                prefix
+            };
+            // 只跳过确实不存在的前缀；网络、鉴权等错误照常上报
+            let folder_id = match self
+                .resolve_remote_folder_from_current(token, drive_id, &prefix)
+                .await
+            {
+                Ok(folder_id) => folder_id,
+                Err(err) if err.is::<NotFound>() => continue,
+                Err(err) => return Err(err),
+            };
+            let rest: Vec<String> = comps[split..].iter().map(|c| c.to_string()).collect();
+            let mut matches =
+                glob::expand_in(&self.cache, token, drive_id, &folder_id, &rest, dir_only).await?;
+            matches.sort();
+            let base = if prefix.is_empty() || prefix.ends_with('/') {
+                prefix
+            } else {
+                format!("{}/", prefix)
+            };
+            results.extend(matches.into_iter().map(|m| format!("{}{}", base, m)));
+        }
+
+        if results.is_empty() {
+            anyhow::bail!("No match: {}", pattern);
+        }
+        Ok(results)
+    }
+
     async fn ensure_drive_id(&mut self, token: &str) -> Result<String> {
-        if let Some(id) = &self.drive_id_cache {
-            Ok(id.clone())
+        let cached = self.drive_id.lock().unwrap().clone();
+        if let Some(id) = cached {
+            Ok(id)
         } else {
             let id = get_drive_id(token).await?;
-            self.drive_id_cache = Some(id.clone());
+            *self.drive_id.lock().unwrap() = Some(id.clone());
             Ok(id)
         }
     }
@@ -155,11 +344,39 @@ impl Shell {
         Ok((token, drive_id))
     }
 
-    /// Keep the auto-completer aware of the current remote folder id.
-    /// 同步当前远程目录 ID，供自动补全使用。
-    async fn sync_completer_remote_cwd(&self) {
-        let mut guard = self.completer_remote_cwd.lock().await;
-        *guard = self.remote_cwd.clone();
+    /// Keep the auto-completer aware of the current remote folder stack.
+    /// 同步当前远程目录栈，供自动补全解析相对路径与 `..`。
+    fn sync_completer_remote_stack(&self) {
+        let mut guard = self.completer_remote_stack.lock().unwrap();
+        *guard = self.remote_stack.clone();
+    }
+
+    /// Warm the listing cache for a folder in the background so that TAB
+    /// completion right after `cd` does not wait on the network. The drive
+    /// is resolved first when no command has done so yet (e.g. at startup).
+    /// 后台预取目录列表，使 `cd` 之后的 TAB 补全无需等待网络；尚未获取 drive_id
+    /// 时（如刚启动）先在后台获取。
+    fn prefetch_listing(&self, folder_id: &str) {
+        let Ok(token) = check_login() else {
+            return;
+        };
+        let shared_drive_id = Arc::clone(&self.drive_id);
+        let cache = self.cache.clone();
+        let folder_id = folder_id.to_string();
+        tokio::spawn(async move {
+            let cached = shared_drive_id.lock().unwrap().clone();
+            let drive_id = match cached {
+                Some(id) => id,
+                None => match get_drive_id(&token).await {
+                    Ok(id) => {
+                        *shared_drive_id.lock().unwrap() = Some(id.clone());
+                        id
+                    }
+                    Err(_) => return,
+                },
+            };
+            let _ = cache.items(&token, &drive_id, &folder_id).await;
+        });
     }
 
     /// Handle `ls` command with optional path argument (relative or absolute).
@@ -168,8 +385,13 @@ impl Shell {
         let (token, drive_id) = self.ensure_auth().await?;
 
         let mut options = ListOptions::default();
-        let mut path: Option<String> = None;
-        let mut iter = args.into_iter();
+        let mut paths: Vec<String> = Vec::new();
+        let mut long = false;
+        let mut human = false;
+        let mut columns: Option<Vec<Column>> = None;
+        let mut machine: Option<ListFormat> = None;
+        let mut no_pager = false;
+        let mut iter = split_short_flags(args, "lhaR").into_iter();
 
         while let Some(arg) = iter.next() {
             match arg.as_str() {
@@ -217,27 +439,190 @@ impl Shell {
                 "--all" | "-a" => {
                     options.fetch_all = true;
                 }
+                "--long" | "-l" => long = true,
+                "--recursive" | "-R" => options.recursive = true,
+                "--human-readable" | "-h" => human = true,
+                "--columns" => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--columns requires a value"))?;
+                    columns = Some(Column::parse_list(&value)?);
+                    long = true;
+                }
+                "--json" | "--jsonl" | "--csv" => machine = ListFormat::from_flag(&arg),
+                "--no-pager" => no_pager = true,
+                "--type" => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--type requires a value"))?;
+                    if value != "file" && value != "folder" {
+                        anyhow::bail!("--type must be file or folder");
+                    }
+                    options.kind = Some(value);
+                }
+                "--category" => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--category requires a value"))?;
+                    if let Some(bad) = value.split(',').find(|c| !CATEGORIES.contains(c)) {
+                        anyhow::bail!(
+                            "Unsupported --category value: {} (allowed: {})",
+                            bad,
+                            CATEGORIES.join(", ")
+                        );
+                    }
+                    options.category = Some(value);
+                }
+                "--ext" => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--ext requires a value"))?;
+                    options.filter.extensions = ItemFilter::parse_extensions(&value);
+                }
+                "--min-size" | "--max-size" => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("{} requires a value", arg))?;
+                    let size = Some(parse_size(&value)?);
+                    if arg == "--min-size" {
+                        options.filter.min_size = size;
+                    } else {
+                        options.filter.max_size = size;
+                    }
+                }
+                "--newer" | "--older" => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("{} requires a value", arg))?;
+                    let time = Some(parse_time(&value)?);
+                    if arg == "--newer" {
+                        options.filter.newer = time;
+                    } else {
+                        options.filter.older = time;
+                    }
+                }
                 arg if arg.starts_with('-') => {
                     anyhow::bail!("Unknown option for ls: {}", arg);
                 }
-                arg => {
-                    if path.is_some() {
-                        anyhow::bail!("Multiple paths provided to ls: {}", arg);
+                arg => paths.push(arg.to_string()),
+            }
+        }
+
+        if long {
+            let columns = match (columns, &self.config.ls_columns) {
+                (Some(columns), _) => columns,
+                (None, Some(list)) => Column::parse_list(list)?,
+                (None, None) => DEFAULT_COLUMNS.to_vec(),
+            };
+            options.format = ListFormat::Long { columns, human };
+        }
+        if let Some(format) = machine {
+            options.format = format;
+        }
+        options.page = !no_pager && !options.format.is_machine() && pager::interactive();
+
+        if paths.is_empty() && !options.recursive {
+            return list_remote_files(
+                &token,
+                &drive_id,
+                &self.remote_cwd,
+                &self.remote_path,
+                &options,
+            )
+            .await;
+        }
+
+        if paths.is_empty() {
+            paths.push(".".to_string());
+        }
+
+        // 与 GNU ls 一致：先列出文件，再逐个列出文件夹内容
+        let mut folders = Vec::new();
+        let mut files = Vec::new();
+        for target in &paths {
+            match self
+                .resolve_remote_folder_from_current(&token, &drive_id, target)
+                .await
+            {
+                Ok(id) => folders.push((target.clone(), id)),
+                Err(_) => {
+                    let item = self.resolve_remote_item(&token, &drive_id, target).await?;
+                    if item.kind == "folder" {
+                        folders.push((target.clone(), item.file_id));
+                    } else if options.matches(&item) {
+                        files.push((target.clone(), item));
                     }
-                    path = Some(arg.to_string());
                 }
             }
         }
 
-        let target = path.as_deref().unwrap_or(".");
-        let parent_id = if target == "." {
-            self.remote_cwd.clone()
-        } else {
-            self.resolve_remote_parent(&token, &drive_id, target)
-                .await?
-        };
+        if options.format.is_machine()
+            && (options.recursive || paths.len() > 1 || !files.is_empty())
+        {
+            return self
+                .print_ls_listing(&token, &drive_id, &files, &folders, &options)
+                .await;
+        }
+
+        let file_items: Vec<FileItem> = files.into_iter().map(|(_, item)| item).collect();
+        print_items(&file_items, &options.format);
+
+        let show_headers = paths.len() > 1;
+        for (i, (target, folder_id)) in folders.iter().enumerate() {
+            if options.recursive {
+                if i > 0 || !file_items.is_empty() {
+                    println!();
+                }
+                list_recursive(&token, &drive_id, folder_id, target, &options).await?;
+                continue;
+            }
+            if show_headers {
+                if i > 0 || folders.len() < paths.len() {
+                    println!();
+                }
+                println!("{}:", target.bold());
+            }
+            let folder_path = self.absolute_remote_path(target);
+            list_remote_files(&token, &drive_id, folder_id, &folder_path, &options).await?;
+        }
+        Ok(())
+    }
+
+    /// Emit one machine-readable listing covering several `ls` targets or a
+    /// recursive walk. A single marker cannot describe several folders, so
+    /// each is read in full.
+    /// 多个目标或递归列出时合并为一份机器可读输出；每个文件夹都完整拉取，不再输出分页标记。
+    async fn print_ls_listing(
+        &self,
+        token: &str,
+        drive_id: &str,
+        files: &[(String, FileItem)],
+        folders: &[(String, String)],
+        options: &ListOptions,
+    ) -> Result<()> {
+        let depth = if options.recursive { None } else { Some(1) };
+        let mut contents = Vec::new();
+        for (target, folder_id) in folders {
+            let listings = walk(token, drive_id, folder_id, options, depth, None).await?;
+            contents.push((self.absolute_remote_path(target), folder_id, listings));
+        }
+
+        let mut records = Vec::new();
+        for (target, item) in files {
+            let path = self.absolute_remote_path(target);
+            let dir = path.rsplit_once('/').map_or("", |(dir, _)| dir);
+            records.push(Record::new(item, dir));
+        }
+        for (dir, folder_id, listings) in &contents {
+            collect_records(listings, folder_id, dir, options, &mut records);
+        }
 
-        list_remote_files(&token, &drive_id, &parent_id, &options).await
+        let listing = Listing {
+            items: records,
+            next_marker: None,
+            total_count: None,
+        };
+        print_listing(&listing, &options.format)
     }
 
     async fn cmd_search(&mut self, args: Vec<String>) -> Result<()> {
@@ -254,6 +639,7 @@ impl Shell {
         let mut raw_query: Option<String> = None;
         let mut global = false;
         let mut target_path: Option<String> = None;
+        let mut no_pager = false;
         let mut iter = args.into_iter();
 
         while let Some(arg) = iter.next() {
@@ -300,6 +686,10 @@ impl Shell {
                     options.order_direction = Some(upper);
                 }
                 "--return-total" => options.return_total_count = true,
+                "--no-pager" => no_pager = true,
+                "--json" | "--jsonl" | "--csv" => {
+                    options.format = ListFormat::from_flag(&arg).unwrap_or_default();
+                }
                 "--all" | "-a" => options.fetch_all = true,
                 "--global" => global = true,
                 "--in" | "--path" => {
@@ -353,17 +743,325 @@ impl Shell {
             }
         };
 
+        options.page = !no_pager && !options.format.is_machine() && pager::interactive();
         search_files(&token, &drive_id, &query, &options).await
     }
 
+    async fn cmd_tree(&mut self, args: Vec<String>) -> Result<()> {
+        let (token, drive_id) = self.ensure_auth().await?;
+
+        let mut options = TreeOptions::default();
+        let mut target: Option<String> = None;
+        let mut no_pager = false;
+        let mut iter = args.into_iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "-L" => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("-L requires a value"))?;
+                    let depth: usize = value
+                        .parse()
+                        .ok()
+                        .filter(|d| *d > 0)
+                        .ok_or_else(|| anyhow::anyhow!("Invalid -L value: {}", value))?;
+                    options.max_depth = Some(depth);
+                }
+                "-d" => options.dirs_only = true,
+                "--no-pager" => no_pager = true,
+                arg if arg.starts_with('-') => {
+                    anyhow::bail!("Unknown option for tree: {}", arg);
+                }
+                arg => {
+                    if target.is_some() {
+                        anyhow::bail!("Usage: tree [-L depth] [-d] [path]");
+                    }
+                    target = Some(arg.to_string());
+                }
+            }
+        }
+
+        let target = target.unwrap_or_else(|| ".".to_string());
+        let folder_id = self
+            .resolve_remote_folder_from_current(&token, &drive_id, &target)
+            .await?;
+        options.page = !no_pager && pager::interactive();
+        print_tree(&token, &drive_id, &folder_id, &target, &options).await
+    }
+
+    async fn cmd_du(&mut self, args: Vec<String>) -> Result<()> {
+        let (token, drive_id) = self.ensure_auth().await?;
+
+        let mut options = DuOptions::default();
+        let mut target: Option<String> = None;
+        let mut iter = split_short_flags(args, "sh").into_iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "-s" => options.summarize = true,
+                "-h" => options.human = true,
+                "-d" => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("-d requires a value"))?;
+                    let depth: usize = value
+                        .parse()
+                        .map_err(|_| anyhow::anyhow!("Invalid -d value: {}", value))?;
+                    options.max_depth = Some(depth);
+                }
+                arg if arg.starts_with('-') => {
+                    anyhow::bail!("Unknown option for du: {}", arg);
+                }
+                arg => {
+                    if target.is_some() {
+                        anyhow::bail!("Usage: du [-s] [-d depth] [-h] [path]");
+                    }
+                    target = Some(arg.to_string());
+                }
+            }
+        }
+
+        let target = target.unwrap_or_else(|| ".".to_string());
+        let folder_id = self
+            .resolve_remote_folder_from_current(&token, &drive_id, &target)
+            .await?;
+        print_disk_usage(&token, &drive_id, &folder_id, &target, &options).await
+    }
+
+    /// `cat`, `head` and `tail`: stream remote files to stdout. `head` and
+    /// `tail` print coreutils-style `==> name <==` headers for several files.
+    async fn cmd_cat(&mut self, cmd: &str, args: Vec<String>) -> Result<()> {
+        let usage = match cmd {
+            "cat" => "Usage: cat [--range start-end] <path>...",
+            _ => "Usage: head|tail [-n lines | -c bytes] <path>...",
+        };
+        let mut portion = match cmd {
+            "head" => Some(Portion::FirstLines(10)),
+            "tail" => Some(Portion::LastLines(10)),
+            _ => None,
+        };
+        let mut targets = Vec::new();
+        let mut iter = args.into_iter();
+        while let Some(arg) = iter.next() {
+            let option = arg.as_str();
+            match (cmd, option) {
+                ("cat", "--range") | ("head" | "tail", "-n" | "-c") => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("{} requires a value", option))?;
+                    portion = Some(match option {
+                        "--range" => Portion::parse_range(&value)?,
+                        "-c" => {
+                            let count = parse_size(&value)?;
+                            if cmd == "head" {
+                                Portion::FirstBytes(count)
+                            } else {
+                                Portion::LastBytes(count)
+                            }
+                        }
+                        _ => {
+                            let count = value
+                                .parse()
+                                .map_err(|_| anyhow::anyhow!("Invalid -n value: {}", value))?;
+                            if cmd == "head" {
+                                Portion::FirstLines(count)
+                            } else {
+                                Portion::LastLines(count)
+                            }
+                        }
+                    });
+                }
+                (_, arg) if arg.starts_with('-') && arg.len() > 1 => {
+                    anyhow::bail!("Unknown option for {}: {}", cmd, arg);
+                }
+                (_, arg) => targets.push(arg.to_string()),
+            }
+        }
+        if targets.is_empty() {
+            anyhow::bail!(usage);
+        }
+
+        let (token, drive_id) = self.ensure_auth().await?;
+        let headers = cmd != "cat" && targets.len() > 1;
+        for (i, target) in targets.iter().enumerate() {
+            let item = self.resolve_remote_item(&token, &drive_id, target).await?;
+            if item.kind == "folder" {
+                anyhow::bail!("{}: '{}' is a folder", cmd, target);
+            }
+            if headers {
+                let blank = if i > 0 { "\n" } else { "" };
+                println!("{}==> {} <==", blank, target);
+            }
+            cat(&token, &drive_id, &item, portion.unwrap_or(Portion::All)).await?;
+        }
+        Ok(())
+    }
+
+    async fn cmd_stat(&mut self, args: Vec<String>) -> Result<()> {
+        let mut json = false;
+        let mut targets = Vec::new();
+        for arg in args {
+            match arg.as_str() {
+                "--json" => json = true,
+                arg if arg.starts_with('-') => {
+                    anyhow::bail!("Unknown option for stat: {}", arg);
+                }
+                arg => targets.push(arg.to_string()),
+            }
+        }
+        if targets.is_empty() {
+            anyhow::bail!("Usage: stat [--json] <path>...");
+        }
+
+        let (token, drive_id) = self.ensure_auth().await?;
+        let mut details = Vec::new();
+        for (i, target) in targets.iter().enumerate() {
+            let file_id = match self.resolve_remote_item(&token, &drive_id, target).await {
+                Ok(item) => item.file_id,
+                Err(err) => self
+                    .resolve_remote_folder_from_current(&token, &drive_id, target)
+                    .await
+                    .map_err(|_| err)?,
+            };
+            let detail = remote::stat::get_file_detail(&token, &drive_id, &file_id).await?;
+            let path = self.absolute_remote_path(target);
+            if json {
+                details.push((detail, path));
+            } else {
+                if i > 0 {
+                    println!();
+                }
+                remote::stat::print_detail(&detail, &path);
+            }
+        }
+        if json {
+            remote::stat::print_details_json(&details)?;
+        }
+        Ok(())
+    }
+
     async fn cmd_cd(&mut self, parts: Vec<String>) -> Result<()> {
         if parts.is_empty() {
             return Err(anyhow::anyhow!("Usage: cd <folder>"));
         }
         let target = &parts[0];
+        if target == "-" {
+            let prev = self
+                .prev_remote_stack
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("No previous directory"))?;
+            self.set_remote_stack(prev);
+            println!("{}", self.remote_path);
+            return Ok(());
+        }
         let (token, drive_id) = self.ensure_auth().await?;
-        self.navigate_remote_path(&token, &drive_id, target).await?;
-        self.sync_completer_remote_cwd().await;
+        self.navigate_remote_path(&token, &drive_id, target).await
+    }
+
+    /// `pushd <path>` saves the current folder and enters `path`;
+    /// `pushd` alone swaps the current folder with the top of the stack.
+    /// 目录栈：`pushd <路径>` 保存当前目录并进入新目录；无参数时交换栈顶。
+    async fn cmd_pushd(&mut self, args: Vec<String>) -> Result<()> {
+        let new_stack = match args.first() {
+            Some(target) => {
+                let (token, drive_id) = self.ensure_auth().await?;
+                self.resolve_remote_stack(&token, &drive_id, target).await?
+            }
+            None => self
+                .dir_stack
+                .pop()
+                .ok_or_else(|| anyhow::anyhow!("pushd: directory stack is empty"))?,
+        };
+        self.dir_stack.push(self.remote_stack.clone());
+        self.set_remote_stack(new_stack);
+        self.cmd_dirs();
+        Ok(())
+    }
+
+    /// Return to the folder on top of the directory stack.
+    /// 弹出目录栈顶并切换过去。
+    async fn cmd_popd(&mut self, _args: Vec<String>) -> Result<()> {
+        let stack = self
+            .dir_stack
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("popd: directory stack is empty"))?;
+        self.set_remote_stack(stack);
+        self.cmd_dirs();
+        Ok(())
+    }
+
+    fn cmd_dirs(&self) {
+        let mut line = vec![self.remote_path.clone()];
+        for stack in self.dir_stack.iter().rev() {
+            if let Some((path, _)) = stack.last() {
+                line.push(path.clone());
+            }
+        }
+        println!("{}", line.join(" "));
+    }
+
+    /// Manage persistent bookmarks: `bookmark add|rm|ls|go <name>`.
+    /// 书签管理：按 file_id 保存，文件夹改名后依然可以跳转。
+    async fn cmd_bookmark(&mut self, args: Vec<String>) -> Result<()> {
+        let usage = "Usage: bookmark add <name> [path] | rm <name> | ls | go <name>";
+        let sub = args.first().map(String::as_str).unwrap_or("ls");
+        match sub {
+            "ls" | "list" => {
+                if self.bookmarks.iter().next().is_none() {
+                    println!("{}", "(no bookmarks)".dimmed());
+                }
+                for (name, bookmark) in self.bookmarks.iter() {
+                    println!("{:<20} {}", name.green(), bookmark.path);
+                }
+            }
+            "add" => {
+                let name = args.get(1).ok_or_else(|| anyhow::anyhow!(usage))?;
+                let stack = match args.get(2) {
+                    Some(target) => {
+                        let (token, drive_id) = self.ensure_auth().await?;
+                        self.resolve_remote_stack(&token, &drive_id, target).await?
+                    }
+                    None => self.remote_stack.clone(),
+                };
+                let (path, file_id) = stack
+                    .last()
+                    .cloned()
+                    .ok_or_else(|| anyhow::anyhow!("Failed to resolve remote path"))?;
+                println!("🔖 Bookmark '{}' -> {}", name, path);
+                self.bookmarks.insert(name, Bookmark { file_id, path })?;
+            }
+            "rm" | "remove" => {
+                let name = args.get(1).ok_or_else(|| anyhow::anyhow!(usage))?;
+                if !self.bookmarks.remove(name)? {
+                    anyhow::bail!("No such bookmark: {}", name);
+                }
+                println!("🗑️  Bookmark '{}' removed", name);
+            }
+            "go" => {
+                let name = args.get(1).ok_or_else(|| anyhow::anyhow!(usage))?;
+                let bookmark = self
+                    .bookmarks
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| anyhow::anyhow!("No such bookmark: {}", name))?;
+                let (token, drive_id) = self.ensure_auth().await?;
+                let stack =
+                    remote::stat::get_folder_stack(&token, &drive_id, &bookmark.file_id).await?;
+                let path = stack.last().map(|(p, _)| p.clone()).unwrap_or_default();
+                if path != bookmark.path {
+                    // 文件夹被移动或重命名：刷新记录的路径
+                    self.bookmarks.insert(
+                        name,
+                        Bookmark {
+                            file_id: bookmark.file_id,
+                            path,
+                        },
+                    )?;
+                }
+                self.set_remote_stack(stack);
+                println!("{}", self.remote_path);
+            }
+            _ => anyhow::bail!(usage),
+        }
         Ok(())
     }
 
@@ -371,14 +1069,38 @@ impl Shell {
         println!("{}", "Available commands:".blue());
         println!("  login              OAuth2 login");
         println!("  ls [path]         Remote listing (支持相对/绝对路径)");
-        println!("  cd <path>         Remote navigation (支持..与绝对路径)");
+        println!("    -l / -h / --columns type,size,created,updated,name,id,hash,mime  长格式");
+        println!("    --json / --jsonl / --csv  机器可读输出（search 同样支持）");
+        println!("    -R                递归列出子目录");
+        println!("    --no-pager        不分页（ls/search/tree 在终端中默认分页：空格翻页，q 退出；设置 $PAGER 时交给它）");
+        println!("    --type file|folder --category video,image,.. --ext mp4,mkv  过滤");
+        println!("    --min-size/--max-size 10M  --newer/--older 2024-05-01|7d  过滤");
+        println!("  cd <path>         Remote navigation (支持..与绝对路径, cd - 返回上一目录)");
+        println!("  pushd/popd/dirs   Remote directory stack");
+        println!("  bookmark add|rm|ls|go <name>  Persistent folder bookmarks");
         println!("  pwd               Show remote cwd");
         println!("  search <keyword>  Search files (支持 --global/--limit/--all 等)");
-        println!("  put <file>         Upload file");
-        println!("  get <name> [path]  Download file");
-        println!("  cp <name> <to>     Copy remote file");
-        println!("  mv <name> <to>     Move/rename remote file");
-        println!("  rm <name>          Delete remote file");
+        println!("  tree [-L depth] [-d] [path]  Show folder hierarchy");
+        println!("  stat/info [--json] <path>...  Show full file metadata");
+        println!("  du [-s] [-d depth] [-h] [path]  Folder sizes, largest first");
+        println!("  df                Space usage and membership");
+        println!("  set bandwidth <rate|off>  Cap all transfers this session (会话级限速)");
+        println!("  put [--limit-rate 5M] <file>  Upload file");
+        println!("  get <src>... [dir]  Download files (多个来源时最后一个参数为本地目录)");
+        println!("  get -r <folder> [dir]  Download a folder recursively");
+        println!("  get --no-verify ...  Skip the size/SHA1 check after download (跳过校验)");
+        println!("  get --connections n|auto --chunk-size 16M ...  Tune parallel chunk downloads");
+        println!("  get --limit-rate 5M ...  Cap the download rate (限速)");
+        println!("  get --resume|--force|--skip-existing|--skip-if-same|--rename ...");
+        println!(
+            "                      What to do with existing files (默认续传 .part，已存在时询问)"
+        );
+        println!("  cat [--range a-b] <path>...  Print remote files to stdout (输出文件内容)");
+        println!("  head|tail [-n lines|-c bytes] <path>...  First/last part of remote files");
+        println!("  cp <src>... <to>   Copy remote files");
+        println!("  mv <src>... <to>   Move/rename remote files");
+        println!("  rm <path>...       Delete remote files");
+        println!("  (ls/get/rm/cp/mv/stat 支持通配符 * ? [..] {{a,b}} **，加引号可关闭)");
         println!("  lls / lcd / lpwd   Local file ops");
         println!("  exit / quit        Exit");
     }
@@ -411,103 +1133,368 @@ impl Shell {
         Ok(())
     }
 
+    /// `set` shows session settings; `set bandwidth <rate|off>` caps the
+    /// combined rate of every transfer for the rest of the session.
+    fn cmd_set(&mut self, args: Vec<String>) -> Result<()> {
+        let describe = |rate: Option<u64>| match rate {
+            Some(rate) => format!("{}/s", human_size(rate)),
+            None => "unlimited".to_string(),
+        };
+        match &args[..] {
+            [] => {
+                println!("bandwidth: {}", describe(remote::bandwidth::session_rate()));
+                if let Some(rate) = remote::bandwidth::scheduled_rate() {
+                    println!("scheduled: {}", describe(Some(rate)));
+                }
+            }
+            [key, value] if key == "bandwidth" => {
+                let rate = parse_rate(value)?;
+                remote::bandwidth::set_session_rate(rate);
+                println!("🚦 Bandwidth limit: {}", describe(rate));
+            }
+            _ => anyhow::bail!("Usage: set [bandwidth <rate|off>]"),
+        }
+        Ok(())
+    }
+
     async fn cmd_put(&mut self, args: Vec<String>) -> Result<()> {
-        if args.is_empty() {
-            return Err(anyhow::anyhow!("Usage: put <local_file>"));
+        let mut limit_rate = None;
+        let mut paths = Vec::new();
+        let mut iter = args.into_iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--limit-rate" => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--limit-rate requires a value"))?;
+                    limit_rate = parse_rate(&value)?;
+                }
+                _ => paths.push(arg),
+            }
         }
+        let [path] = &paths[..] else {
+            return Err(anyhow::anyhow!("Usage: put [--limit-rate 5M] <local_file>"));
+        };
         let (token, drive_id) = self.ensure_auth().await?;
-        let local_path = expand_local_path(&args[0]);
+        let local_path = expand_local_path(path);
         let path_str = local_path.to_string_lossy().to_string();
-        remote::put::put_file(&token, &drive_id, &self.remote_cwd, &path_str).await
+        let result =
+            remote::put::put_file(&token, &drive_id, &self.remote_cwd, &path_str, limit_rate).await;
+        self.cache.invalidate(&self.remote_cwd);
+        result
     }
 
     async fn cmd_get(&mut self, args: Vec<String>) -> Result<()> {
-        if args.is_empty() {
-            return Err(anyhow::anyhow!("Usage: get <filename> [local_path]"));
+        let mut recursive = false;
+        let mut options = DownloadOptions::default();
+        if let Some(value) = &self.config.connections {
+            options.connections = Connections::parse(&value.to_string())?;
         }
-        let remote_name = &args[0];
-        let local_path = if args.len() >= 2 {
-            let provided = expand_local_path(&args[1]);
-            if provided.is_dir() {
-                provided.join(remote_name)
-            } else {
-                provided
+        if let Some(value) = &self.config.chunk_size {
+            options.chunk_size = parse_chunk_size(&value.to_string())?;
+        }
+        let mut paths = Vec::new();
+        let mut iter = args.into_iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--connections" => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--connections requires a value"))?;
+                    options.connections = Connections::parse(&value)?;
+                }
+                "--chunk-size" => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--chunk-size requires a value"))?;
+                    options.chunk_size = parse_chunk_size(&value)?;
+                }
+                "--limit-rate" => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--limit-rate requires a value"))?;
+                    options.limit_rate = parse_rate(&value)?;
+                }
+                "-r" | "--recursive" => recursive = true,
+                "--no-verify" => options.verify = false,
+                "--resume" | "--force" | "--skip-existing" | "--skip-if-same" | "--rename" => {
+                    let policy = match arg.as_str() {
+                        "--resume" => ExistingPolicy::Resume,
+                        "--force" => ExistingPolicy::Force,
+                        "--skip-existing" => ExistingPolicy::SkipExisting,
+                        "--skip-if-same" => ExistingPolicy::SkipIfSame,
+                        _ => ExistingPolicy::Rename,
+                    };
+                    if options.policy.is_some_and(|p| p != policy) {
+                        anyhow::bail!(
+                            "get: --resume, --force, --skip-existing, --skip-if-same and --rename are mutually exclusive"
+                        );
+                    }
+                    options.policy = Some(policy);
+                }
+                arg if arg.starts_with('-') => {
+                    anyhow::bail!("Unknown option for get: {}", arg);
+                }
+                arg => paths.push(arg.to_string()),
             }
+        }
+        if paths.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Usage: get [-r] [--resume|--force|--skip-existing|--skip-if-same|--rename] [--no-verify] [--connections n|auto] [--chunk-size size] [--limit-rate rate] <remote_path>... [local_path]"
+            ));
+        }
+        let (token, drive_id) = self.ensure_auth().await?;
+
+        // 与 cp 一致：多个来源时最后一个参数必须是本地目录
+        let (sources, target) = if paths.len() == 1 {
+            (&paths[..], None)
         } else {
-            self.local_cwd.join(remote_name)
+            let (sources, target) = paths.split_at(paths.len() - 1);
+            (sources, Some(expand_local_path(&target[0])))
+        };
+        if sources.len() > 1 && !target.as_ref().is_some_and(|t| t.is_dir()) {
+            anyhow::bail!(
+                "get: target '{}' is not a directory",
+                paths[paths.len() - 1]
+            );
+        }
+        let local_for = |name: &str| match &target {
+            Some(target) if target.is_dir() => target.join(name),
+            Some(target) => target.clone(),
+            None => self.local_cwd.join(name),
         };
 
-        if local_path.exists() {
-            return Err(anyhow::anyhow!(
-                "Local file already exists: {}",
-                local_path.display()
-            ));
+        let mut dirs = Vec::new();
+        let mut jobs = Vec::new();
+        let mut failed = 0;
+        for source in sources {
+            let planned = self
+                .plan_download(&token, &drive_id, source, recursive, &local_for)
+                .await;
+            match planned {
+                Ok((mut source_dirs, mut source_jobs)) => {
+                    dirs.append(&mut source_dirs);
+                    jobs.append(&mut source_jobs);
+                }
+                Err(e) => {
+                    eprintln!("{} {}: {}", "Error:".red(), source, e);
+                    failed += 1;
+                }
+            }
         }
 
-        if let Some(parent) = local_path.parent() {
-            if !parent.as_os_str().is_empty() && !parent.exists() {
-                return Err(anyhow::anyhow!(
-                    "Local directory does not exist: {}",
-                    parent.display()
-                ));
+        if let [job] = &jobs[..] {
+            if dirs.is_empty() && sources.len() == 1 {
+                if let Some(parent) = job.local_path.parent() {
+                    if !parent.as_os_str().is_empty() && !parent.exists() {
+                        anyhow::bail!("Local directory does not exist: {}", parent.display());
+                    }
+                }
             }
         }
+        for dir in &dirs {
+            fs::create_dir_all(dir)?;
+        }
 
-        let (token, drive_id) = self.ensure_auth().await?;
-        remote::get::get_file(
-            &token,
-            &drive_id,
-            &self.remote_cwd,
-            remote_name,
-            &local_path,
+        let total = jobs.len() + failed;
+        let summary = download(&token, &drive_id, jobs, &options).await;
+        if total > 1 || !dirs.is_empty() {
+            println!(
+                "📦 {} transferred, {} skipped, {} failed",
+                summary.transferred,
+                summary.skipped,
+                summary.failed + failed
+            );
+        }
+        batch_result("get", summary.failed + failed, total)
+    }
+
+    /// Work out what downloading one `get` source involves: the local
+    /// directories to create (for `-r`) and the files to fetch.
+    /// 解析单个下载来源：需要创建的本地目录（-r 时）与待下载文件。
+    async fn plan_download(
+        &self,
+        token: &str,
+        drive_id: &str,
+        source: &str,
+        recursive: bool,
+        local_for: &impl Fn(&str) -> PathBuf,
+    ) -> Result<(Vec<PathBuf>, Vec<DownloadJob>)> {
+        let remote_path = self.absolute_remote_path(source);
+        let (folder_id, name) = match self.resolve_remote_item(token, drive_id, source).await {
+            Ok(item) if item.kind != "folder" => {
+                let job = DownloadJob::new(&item, remote_path, local_for(&item.name));
+                return Ok((Vec::new(), vec![job]));
+            }
+            Ok(item) => (item.file_id, item.name),
+            Err(err) => {
+                let id = self
+                    .resolve_remote_folder_from_current(token, drive_id, source)
+                    .await
+                    .map_err(|_| err)?;
+                let name = remote_path.rsplit('/').next().unwrap_or("root").to_string();
+                (id, name)
+            }
+        };
+        if !recursive {
+            anyhow::bail!("'{}' is a folder (use get -r)", source);
+        }
+
+        println!("🔍 Scanning {}...", remote_path);
+        let listings = walk(
+            token,
+            drive_id,
+            &folder_id,
+            &ListOptions::default(),
+            None,
+            None,
         )
-        .await
+        .await?;
+        let mut dirs = Vec::new();
+        let mut jobs = Vec::new();
+        plan_folder(
+            &listings,
+            &folder_id,
+            &remote_path,
+            &local_for(&name),
+            &mut dirs,
+            &mut jobs,
+        );
+        Ok((dirs, jobs))
     }
 
     async fn cmd_cp(&mut self, args: Vec<String>) -> Result<()> {
         if args.len() < 2 {
-            return Err(anyhow::anyhow!("Usage: cp <filename> <target>"));
+            return Err(anyhow::anyhow!("Usage: cp <source>... <target>"));
         }
         let (token, drive_id) = self.ensure_auth().await?;
-        let (to_parent, new_name) = self
-            .resolve_remote_destination(&token, &drive_id, &args[1], &args[0])
+        let (sources, target) = args.split_at(args.len() - 1);
+        let target = &target[0];
+
+        if let [source] = sources {
+            let item = self.resolve_remote_item(&token, &drive_id, source).await?;
+            let (to_parent, new_name) = self
+                .resolve_remote_destination(&token, &drive_id, target, &item.name)
+                .await?;
+            let result = remote::cp::copy_file(
+                &token,
+                &drive_id,
+                &item.file_id,
+                &item.name,
+                &to_parent,
+                &new_name,
+            )
+            .await;
+            self.cache.invalidate(&to_parent);
+            return result;
+        }
+
+        // 多个源：目标必须是文件夹
+        let to_parent = self
+            .resolve_remote_folder_from_current(&token, &drive_id, target)
             .await?;
-        remote::cp::copy_file(
-            &token,
-            &drive_id,
-            &self.remote_cwd,
-            &args[0],
-            &to_parent,
-            &new_name,
-        )
-        .await
+        let mut failed = 0;
+        for source in sources {
+            let result = match self.resolve_remote_item(&token, &drive_id, source).await {
+                Ok(item) => {
+                    remote::cp::copy_file(
+                        &token,
+                        &drive_id,
+                        &item.file_id,
+                        &item.name,
+                        &to_parent,
+                        &item.name,
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                eprintln!("{} {}: {}", "Error:".red(), source, e);
+                failed += 1;
+            }
+        }
+        self.cache.invalidate(&to_parent);
+        batch_result("cp", failed, sources.len())
     }
 
     async fn cmd_mv(&mut self, args: Vec<String>) -> Result<()> {
         if args.len() < 2 {
-            return Err(anyhow::anyhow!("Usage: mv <filename> <target>"));
+            return Err(anyhow::anyhow!("Usage: mv <source>... <target>"));
         }
         let (token, drive_id) = self.ensure_auth().await?;
-        let (to_parent, new_name) = self
-            .resolve_remote_destination(&token, &drive_id, &args[1], &args[0])
+        let (sources, target) = args.split_at(args.len() - 1);
+        let target = &target[0];
+
+        if let [source] = sources {
+            let item = self.resolve_remote_item(&token, &drive_id, source).await?;
+            let (to_parent, new_name) = self
+                .resolve_remote_destination(&token, &drive_id, target, &item.name)
+                .await?;
+            let result = remote::mv::move_file(
+                &token,
+                &drive_id,
+                &item.file_id,
+                &item.name,
+                &to_parent,
+                &new_name,
+            )
+            .await;
+            self.cache.invalidate(&to_parent);
+            self.cache.invalidate(&item.parent_file_id);
+            return result;
+        }
+
+        // 多个源：目标必须是文件夹
+        let to_parent = self
+            .resolve_remote_folder_from_current(&token, &drive_id, target)
             .await?;
-        remote::mv::move_file(
-            &token,
-            &drive_id,
-            &self.remote_cwd,
-            &args[0],
-            &to_parent,
-            &new_name,
-        )
-        .await
+        let mut failed = 0;
+        for source in sources {
+            let result = match self.resolve_remote_item(&token, &drive_id, source).await {
+                Ok(item) => {
+                    self.cache.invalidate(&item.parent_file_id);
+                    remote::mv::move_file(
+                        &token,
+                        &drive_id,
+                        &item.file_id,
+                        &item.name,
+                        &to_parent,
+                        &item.name,
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                eprintln!("{} {}: {}", "Error:".red(), source, e);
+                failed += 1;
+            }
+        }
+        self.cache.invalidate(&to_parent);
+        batch_result("mv", failed, sources.len())
     }
 
     async fn cmd_rm(&mut self, args: Vec<String>) -> Result<()> {
         if args.is_empty() {
-            return Err(anyhow::anyhow!("Usage: rm <filename>"));
+            return Err(anyhow::anyhow!("Usage: rm <path>..."));
         }
         let (token, drive_id) = self.ensure_auth().await?;
-        remote::rm::remove_file(&token, &drive_id, &self.remote_cwd, &args[0]).await
+        let mut failed = 0;
+        for path in &args {
+            let result = match self.resolve_remote_item(&token, &drive_id, path).await {
+                Ok(item) => {
+                    self.cache.invalidate(&item.parent_file_id);
+                    remote::rm::remove_file(&token, &drive_id, &item.file_id, &item.name).await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                eprintln!("{} {}: {}", "Error:".red(), path, e);
+                failed += 1;
+            }
+        }
+        batch_result("rm", failed, args.len())
     }
 
     async fn cmd_mkdir(&mut self, args: Vec<String>) -> Result<()> {
@@ -516,7 +1503,32 @@ impl Shell {
         }
         let folder_name = &args[0];
         let (token, drive_id) = self.ensure_auth().await?;
-        remote::mkdir::mkdir(&token, &drive_id, &self.remote_cwd, folder_name).await
+        let result = remote::mkdir::mkdir(&token, &drive_id, &self.remote_cwd, folder_name).await;
+        self.cache.invalidate(&self.remote_cwd);
+        result
+    }
+
+    /// Resolve a remote path (relative or absolute) to the item it names.
+    /// 解析远程路径（相对或绝对），返回其指向的文件或文件夹。
+    async fn resolve_remote_item(
+        &self,
+        token: &str,
+        drive_id: &str,
+        path: &str,
+    ) -> Result<FileItem> {
+        let trimmed = path.trim_end_matches('/');
+        let (parent, name) = match trimmed.rfind('/') {
+            Some(0) => ("/", &trimmed[1..]),
+            Some(idx) => (&trimmed[..idx], &trimmed[idx + 1..]),
+            None => ("", trimmed),
+        };
+        if name.is_empty() || name == "." || name == ".." {
+            anyhow::bail!("'{}' does not name a file", path);
+        }
+        let parent_id = self
+            .resolve_remote_folder_from_current(token, drive_id, parent)
+            .await?;
+        remote::ls::find_item_by_name(&self.cache, token, drive_id, &parent_id, name).await
     }
 
     /// Resolve target path to a parent folder id without changing state.
@@ -545,10 +1557,11 @@ impl Shell {
             let trimmed = relative
                 .trim_start_matches("root/")
                 .trim_start_matches("root");
-            return remote::ls::resolve_path_to_id(token, drive_id, "root", trimmed).await;
+            return remote::ls::resolve_path_to_id(&self.cache, token, drive_id, "root", trimmed)
+                .await;
         }
 
-        remote::ls::resolve_path_to_id(token, drive_id, &self.remote_cwd, target).await
+        remote::ls::resolve_path_to_id(&self.cache, token, drive_id, &self.remote_cwd, target).await
     }
 
     /// Navigate to the target remote folder (relative/absolute, supports `..`).
@@ -559,6 +1572,32 @@ impl Shell {
         drive_id: &str,
         target: &str,
     ) -> Result<()> {
+        let new_stack = self.resolve_remote_stack(token, drive_id, target).await?;
+        self.set_remote_stack(new_stack);
+        Ok(())
+    }
+
+    /// Switch to a new folder stack, remembering the old one for `cd -`.
+    /// 切换到新的目录栈，并记录旧目录供 `cd -` 使用。
+    fn set_remote_stack(&mut self, new_stack: Vec<(String, String)>) {
+        if let Some((path, id)) = new_stack.last().cloned() {
+            let old = std::mem::replace(&mut self.remote_stack, new_stack);
+            self.prev_remote_stack = Some(old);
+            self.remote_path = path;
+            self.remote_cwd = id;
+            self.sync_completer_remote_stack();
+            self.prefetch_listing(&self.remote_cwd);
+        }
+    }
+
+    /// Resolve a target path into the `(path, file_id)` stack it would produce.
+    /// 解析目标路径，得到对应的 (路径, ID) 目录栈（不改变当前状态）。
+    async fn resolve_remote_stack(
+        &self,
+        token: &str,
+        drive_id: &str,
+        target: &str,
+    ) -> Result<Vec<(String, String)>> {
         let mut new_stack = if target.starts_with('/') {
             vec![("/root".to_string(), "root".to_string())]
         } else {
@@ -580,7 +1619,9 @@ impl Shell {
                         .last()
                         .cloned()
                         .unwrap_or_else(|| ("/root".to_string(), "root".to_string()));
-                    if let Some(fid) = get_subfolder_id(token, drive_id, &current_id, name).await? {
+                    if let Some(fid) =
+                        get_subfolder_id(&self.cache, token, drive_id, &current_id, name).await?
+                    {
                         let new_path =
                             if new_stack.last().map(|(p, _)| p == "/root").unwrap_or(true) {
                                 format!("/root/{}", name)
@@ -595,12 +1636,37 @@ impl Shell {
             }
         }
 
-        if let Some((path, id)) = new_stack.last().cloned() {
-            self.remote_stack = new_stack;
-            self.remote_path = path;
-            self.remote_cwd = id;
+        Ok(new_stack)
+    }
+
+    /// Absolute remote path of `target`, resolved lexically against the
+    /// current directory (e.g. `../a` from `/root/x/y` is `/root/x/a`).
+    /// 以当前目录为基准，按字面规则解析出目标的绝对路径。
+    fn absolute_remote_path(&self, target: &str) -> String {
+        let absolute = target.starts_with('/');
+        let mut parts: Vec<&str> = if absolute {
+            Vec::new()
+        } else {
+            self.remote_path
+                .trim_start_matches("/root")
+                .split('/')
+                .filter(|c| !c.is_empty())
+                .collect()
+        };
+        for (i, comp) in target.split('/').filter(|c| !c.is_empty()).enumerate() {
+            match comp {
+                "." => {}
+                ".." => {
+                    parts.pop();
+                }
+                "root" if absolute && i == 0 => {}
+                name => parts.push(name),
+            }
         }
-        Ok(())
+        std::iter::once("/root")
+            .chain(parts)
+            .collect::<Vec<_>>()
+            .join("/")
     }
 
     async fn resolve_remote_folder_from_current(
@@ -635,7 +1701,8 @@ impl Shell {
             if trimmed.is_empty() {
                 return Ok("root".to_string());
             }
-            return remote::ls::resolve_path_to_id(token, drive_id, "root", trimmed).await;
+            return remote::ls::resolve_path_to_id(&self.cache, token, drive_id, "root", trimmed)
+                .await;
         }
 
         let mut stack: Vec<String> = self.remote_stack.iter().map(|(_, id)| id.clone()).collect();
@@ -652,10 +1719,12 @@ impl Shell {
             }
 
             let current_id = stack.last().cloned().unwrap_or_else(|| "root".to_string());
-            if let Some(next_id) = get_subfolder_id(token, drive_id, &current_id, comp).await? {
+            if let Some(next_id) =
+                get_subfolder_id(&self.cache, token, drive_id, &current_id, comp).await?
+            {
                 stack.push(next_id);
             } else {
-                anyhow::bail!("Folder not found: {}", comp);
+                return Err(NotFound(format!("Folder not found: {}", comp)).into());
             }
         }
 
@@ -725,291 +1794,46 @@ impl Shell {
     }
 }
 
-#[derive(Clone)]
-struct AliyunCompleter {
-    remote_cwd: Arc<Mutex<String>>,
-}
-
-impl AliyunCompleter {
-    fn new(remote_cwd: Arc<Mutex<String>>) -> Self {
-        Self { remote_cwd }
-    }
-}
-
-impl Completer for AliyunCompleter {
-    type Candidate = Pair;
-
-    fn complete(
-        &self,
-        line: &str,
-        pos: usize,
-        _ctx: &Context<'_>,
-    ) -> rustyline::Result<(usize, Vec<Pair>)> {
-        let cmds = vec![
-            "ls", "cd", "put", "get", "cp", "mv", "rm", "lls", "lcd", "search", "help", "exit",
-        ];
-        let input = &line[..pos];
-        let mut parts: Vec<&str> = input.split_whitespace().collect();
-        let trailing_ws = input
-            .chars()
-            .last()
-            .map(|c| c.is_whitespace())
-            .unwrap_or(false);
-        if trailing_ws {
-            parts.push("");
-        }
-
-        if parts.is_empty() {
-            return Ok((0, command_pairs("", &cmds)));
-        }
-
-        if parts.len() == 1 {
-            return Ok((0, command_pairs(parts[0], &cmds)));
-        }
-
-        let cmd = parts[0];
-        let arg_index = parts.len() - 1;
-        let current = *parts.last().unwrap_or(&"");
-        let start = input
-            .rfind(|c: char| c.is_whitespace())
-            .map(|i| i + 1)
-            .unwrap_or(0);
-
-        let mut remote_cache: Option<Vec<RemoteEntry>> = None;
-        let mut ensure_remote = || {
-            if remote_cache.is_none() {
-                // 克隆 Arc 以便在新线程中使用
-                let remote_cwd_clone = Arc::clone(&self.remote_cwd);
-
-                // 在新线程中执行所有操作，避免阻塞主运行时
-                if let Ok(result) = std::thread::spawn(move || {
-                    // 在新线程中可以安全使用 blocking_lock
-                    let parent_id = remote_cwd_clone.blocking_lock().clone();
-
-                    // 创建新的运行时
-                    let rt = tokio::runtime::Runtime::new().ok()?;
-                    rt.block_on(fetch_remote_entries(parent_id)).ok()
-                })
-                .join()
-                {
-                    if let Some(entries) = result {
-                        remote_cache = Some(entries);
-                    }
-                }
-            }
-        };
-
-        let pairs = match cmd {
-            "rm" => {
-                ensure_remote();
-                remote_cache
-                    .as_ref()
-                    .map(|entries| remote_name_pairs(entries, current, true, true))
-                    .unwrap_or_default()
-            }
-            "get" => {
-                if arg_index == 1 {
-                    ensure_remote();
-                    remote_cache
-                        .as_ref()
-                        .map(|entries| remote_name_pairs(entries, current, false, true))
-                        .unwrap_or_default()
-                } else {
-                    collect_local_pairs(current)
-                }
+/// Commands whose remote path arguments undergo glob expansion.
+const GLOB_COMMANDS: &[&str] = &[
+    "ls", "get", "cat", "head", "tail", "rm", "cp", "mv", "stat", "info",
+];
+
+/// Expand bundled short flags such as `-lh` into `-l -h` when every letter
+/// is in `letters`.
+fn split_short_flags(args: Vec<String>, letters: &str) -> Vec<String> {
+    args.into_iter()
+        .flat_map(|arg| {
+            let bundle = arg.len() > 2
+                && arg.starts_with('-')
+                && !arg.starts_with("--")
+                && arg[1..].chars().all(|c| letters.contains(c));
+            if bundle {
+                arg[1..].chars().map(|c| format!("-{}", c)).collect()
+            } else {
+                vec![arg]
             }
-            "cp" | "mv" => {
-                if arg_index == 1 {
-                    ensure_remote();
-                    remote_cache
-                        .as_ref()
-                        .map(|entries| remote_name_pairs(entries, current, true, true))
-                        .unwrap_or_default()
-                } else {
-                    ensure_remote();
-                    let mut result = remote_cache
-                        .as_ref()
-                        .map(|entries| remote_name_pairs(entries, current, true, false))
-                        .unwrap_or_default();
-                    result.extend(special_remote_targets(current));
-                    result
-                }
-            }
-            "ls" | "cd" | "mkdir" => {
-                ensure_remote();
-                let mut result = remote_cache
-                    .as_ref()
-                    .map(|entries| remote_name_pairs(entries, current, true, true))
-                    .unwrap_or_default();
-                result.extend(special_remote_targets(current));
-                result
-            }
-            "put" | "lls" | "lcd" => collect_local_pairs(current),
-            _ => vec![],
-        };
-
-        Ok((start, pairs))
-    }
-}
-
-use rustyline::highlight::Highlighter;
-use rustyline::hint::Hinter;
-use rustyline::validate::Validator;
-
-impl Helper for AliyunCompleter {}
-
-impl Hinter for AliyunCompleter {
-    type Hint = String;
-}
-
-impl Highlighter for AliyunCompleter {}
-
-impl Validator for AliyunCompleter {}
-
-#[derive(Clone)]
-struct RemoteEntry {
-    name: String,
-    is_dir: bool,
-}
-
-/// Get remote entries in the provided directory for autocompletion.
-/// 获取指定远程目录下的所有条目（含文件/文件夹），用于命令自动补全。
-async fn fetch_remote_entries(parent_file_id: String) -> Result<Vec<RemoteEntry>> {
-    if let Ok(token) = check_login() {
-        let drive_id = remote::drive::get_drive_id(&token).await?;
-        let url = "https://openapi.alipan.com/adrive/v1.0/openFile/list";
-        let body = serde_json::json!({
-            "drive_id": drive_id,
-            "parent_file_id": parent_file_id
-        });
-
-        let client = reqwest::Client::new();
-        let res = client
-            .post(url)
-            .bearer_auth(token)
-            .json(&body)
-            .send()
-            .await?;
-        let v: serde_json::Value = res.json().await?;
-        if let Some(items) = v["items"].as_array() {
-            let entries = items
-                .iter()
-                .filter_map(|i| {
-                    let name = i["name"].as_str()?;
-                    let kind = i["type"].as_str().unwrap_or("");
-                    Some(RemoteEntry {
-                        name: name.to_string(),
-                        is_dir: kind == "folder",
-                    })
-                })
-                .collect::<Vec<_>>();
-            return Ok(entries);
-        }
-    }
-    Ok(vec![])
-}
-
-fn command_pairs(prefix: &str, commands: &[&str]) -> Vec<Pair> {
-    commands
-        .iter()
-        .filter(|c| c.starts_with(prefix))
-        .map(|c| Pair {
-            display: c.to_string(),
-            replacement: c.to_string(),
         })
         .collect()
 }
 
-fn remote_name_pairs(
-    entries: &[RemoteEntry],
-    prefix: &str,
-    include_dirs: bool,
-    include_files: bool,
-) -> Vec<Pair> {
-    if prefix.contains('/') {
-        return vec![];
-    }
-
-    let mut pairs: Vec<Pair> = entries
-        .iter()
-        .filter(|entry| {
-            (include_dirs || !entry.is_dir)
-                && (include_files || entry.is_dir)
-                && entry.name.starts_with(prefix)
-        })
-        .map(|entry| {
-            let mut replacement = entry.name.clone();
-            if entry.is_dir {
-                replacement.push('/');
-            }
-            Pair {
-                display: replacement.clone(),
-                replacement,
-            }
-        })
-        .collect();
-    pairs.sort_by(|a, b| a.replacement.cmp(&b.replacement));
-    pairs
-}
-
-fn collect_local_pairs(prefix: &str) -> Vec<Pair> {
-    let mut prefix_owned = prefix.to_string();
-    if prefix_owned == "~" {
-        prefix_owned = "~/".to_string();
-    } else if prefix_owned == "$HOME" {
-        prefix_owned = "$HOME/".to_string();
+/// Parse a `--chunk-size` value such as `16M`, between one byte and
+/// [`MAX_CHUNK_SIZE`].
+fn parse_chunk_size(text: &str) -> Result<u64> {
+    match parse_size(text)? {
+        size @ 1..=MAX_CHUNK_SIZE => Ok(size),
+        _ => anyhow::bail!(
+            "Invalid chunk size: {} (must be between 1 and {})",
+            text,
+            human_size(MAX_CHUNK_SIZE)
+        ),
     }
-
-    let (dir_part, file_part) = if prefix_owned.ends_with('/') {
-        (prefix_owned.clone(), String::new())
-    } else if let Some(idx) = prefix_owned.rfind('/') {
-        (
-            prefix_owned[..idx + 1].to_string(),
-            prefix_owned[idx + 1..].to_string(),
-        )
-    } else {
-        (String::new(), prefix_owned.clone())
-    };
-
-    let mut dir_for_fs = if dir_part.is_empty() {
-        ".".to_string()
-    } else {
-        dir_part.trim_end_matches('/').to_string()
-    };
-    if dir_for_fs.is_empty() && dir_part.starts_with('/') {
-        dir_for_fs = "/".to_string();
-    }
-
-    let expanded_dir = expand_local_path(&dir_for_fs);
-    let mut pairs = Vec::new();
-    if let Ok(read_dir) = fs::read_dir(&expanded_dir) {
-        for entry in read_dir.flatten() {
-            let name = entry.file_name().to_string_lossy().to_string();
-            if !file_part.is_empty() && !name.starts_with(&file_part) {
-                continue;
-            }
-            let mut replacement = format!("{}{}", dir_part, name);
-            if entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
-                replacement.push('/');
-            }
-            pairs.push(Pair {
-                display: replacement.clone(),
-                replacement,
-            });
-        }
-    }
-    pairs.sort_by(|a, b| a.replacement.cmp(&b.replacement));
-    pairs
 }
 
-fn special_remote_targets(prefix: &str) -> Vec<Pair> {
-    let specials = ["..", "../", ".", "./"];
-    specials
-        .iter()
-        .filter(|s| s.starts_with(prefix))
-        .map(|s| Pair {
-            display: s.to_string(),
-            replacement: s.to_string(),
-        })
-        .collect()
+/// Summarise a multi-target command: fail if any target failed.
+fn batch_result(cmd: &str, failed: usize, total: usize) -> Result<()> {
+    if failed > 0 {
+        anyhow::bail!("{}: {} of {} targets failed", cmd, failed, total);
+    }
+    Ok(())
 }
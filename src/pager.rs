@@ -0,0 +1,166 @@
+use anyhow::Result;
+use colored::Colorize;
+use console::{Key, Term};
+use std::future::Future;
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+/// Whether listings may page interactively: both ends must be a terminal.
+/// 仅当标准输入和输出都是终端时才启用分页。
+pub fn interactive() -> bool {
+    std::io::stdin().is_terminal() && std::io::stdout().is_terminal()
+}
+
+enum Answer {
+    Page,
+    Line,
+    Quit,
+}
+
+/// Show `prompt` and wait for a key: space/`f` pages, Enter/`j` advances one
+/// line, `q`/Esc quits.
+fn ask(prompt: &str) -> Result<Answer> {
+    let term = Term::stdout();
+    term.write_str(&prompt.reversed().to_string())?;
+    let answer = loop {
+        match term.read_key()? {
+            Key::Char(' ') | Key::Char('f') | Key::PageDown => break Answer::Page,
+            Key::Enter | Key::Char('j') | Key::ArrowDown => break Answer::Line,
+            Key::Char('q') | Key::Char('Q') | Key::Escape => break Answer::Quit,
+            _ => {}
+        }
+    };
+    term.clear_line()?;
+    Ok(answer)
+}
+
+/// 询问是否继续拉取下一页（空格/回车继续，q 退出）。
+/// Ask whether to fetch the next page of results.
+pub fn more() -> Result<bool> {
+    Ok(!matches!(
+        ask("-- More -- (space: next page, q: quit)")?,
+        Answer::Quit
+    ))
+}
+
+/// 一页已渲染的输出及下一页标记。
+/// One page of rendered output, how many items it shows and the marker of
+/// the page after it.
+pub struct Page {
+    pub text: String,
+    pub items: usize,
+    pub next: Option<String>,
+}
+
+/// 按页拉取并显示列表：`fetch_all` 时拉取全部页（交互时最后统一分页），
+/// 否则每页之后询问是否继续，非交互时输出下一页标记。
+/// Fetch and show a listing page by page, starting at `marker`. With
+/// `fetch_all`, every page is fetched and, when `paging` is set, the whole
+/// output is paged at the end. Otherwise each page is followed by a
+/// `-- More --` prompt, or by its next marker when not paging. Pages with no
+/// items (everything filtered out locally) are skipped without a prompt,
+/// and `empty` is shown only when no page had any items.
+pub async fn show_pages<F, Fut>(
+    mut marker: Option<String>,
+    fetch_all: bool,
+    paging: bool,
+    empty: &str,
+    mut fetch: F,
+) -> Result<()>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = Result<Page>>,
+{
+    let collect = fetch_all && paging;
+    let mut collected = String::new();
+    let mut shown = 0;
+    loop {
+        let Page { text, items, next } = fetch(marker.take()).await?;
+        shown += items;
+        if collect {
+            collected.push_str(&text);
+        } else {
+            print!("{}", text);
+        }
+        match next.filter(|m| !m.is_empty()) {
+            // A page the local filters emptied says nothing; keep going.
+            Some(next) if fetch_all || items == 0 => marker = Some(next),
+            Some(next) if paging && more()? => marker = Some(next),
+            Some(next) => {
+                println!("Next marker: {}", next.dimmed());
+                break;
+            }
+            None => break,
+        }
+    }
+    if shown == 0 {
+        let line = format!("{}\n", empty.dimmed());
+        if collect {
+            collected.push_str(&line);
+        } else {
+            print!("{}", line);
+        }
+    }
+    if collect {
+        page(&collected)?;
+    }
+    Ok(())
+}
+
+/// 分页显示一段较长的输出：优先交给 `$PAGER`，否则使用内置分页。
+/// Show long output one screen at a time, through `$PAGER` when it is set
+/// and the built-in pager otherwise. Prints directly when not interactive.
+pub fn page(text: &str) -> Result<()> {
+    if !interactive() {
+        print!("{}", text);
+        return Ok(());
+    }
+    if let Some(pager) = std::env::var("PAGER").ok().filter(|p| !p.trim().is_empty()) {
+        if run_pager(&pager, text).is_ok() {
+            return Ok(());
+        }
+    }
+
+    let rows = usize::from(Term::stdout().size().0).max(2);
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.len() < rows {
+        print!("{}", text);
+        return Ok(());
+    }
+
+    let mut shown = 0;
+    let mut budget = rows - 1;
+    while shown < lines.len() {
+        let end = (shown + budget).min(lines.len());
+        for line in &lines[shown..end] {
+            println!("{}", line);
+        }
+        shown = end;
+        if shown == lines.len() {
+            break;
+        }
+        let percent = shown * 100 / lines.len();
+        budget = match ask(&format!("-- More -- ({}%)", percent))? {
+            Answer::Page => rows - 1,
+            Answer::Line => 1,
+            Answer::Quit => break,
+        };
+    }
+    Ok(())
+}
+
+/// Feed `text` to the user's pager through the shell, so `PAGER="less -R"`
+/// works as it does elsewhere.
+fn run_pager(pager: &str, text: &str) -> Result<()> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(pager)
+        .stdin(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager may exit before reading everything (e.g. `q` in less).
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    child.wait()?;
+    Ok(())
+}
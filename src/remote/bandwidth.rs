@@ -0,0 +1,314 @@
+use anyhow::{anyhow, Result};
+use chrono::{Local, NaiveTime, Timelike};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+use tokio::time::{sleep, Duration};
+
+use super::filter::parse_size;
+
+static SESSION: OnceLock<SessionLimit> = OnceLock::new();
+
+/// 令牌桶：按速率补充令牌，最多积累一秒的量；取用超出时返回需要等待的时间。
+/// A token bucket refilled at `rate` bytes per second that holds at most
+/// one second's worth. Taking more than is available runs into debt, and
+/// the caller waits until the debt is repaid.
+struct TokenBucket {
+    rate: u64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u64) -> Self {
+        Self {
+            rate,
+            tokens: rate as f64,
+            last: Instant::now(),
+        }
+    }
+
+    fn set_rate(&mut self, rate: u64) {
+        if rate != self.rate {
+            self.rate = rate;
+            self.tokens = self.tokens.min(rate as f64);
+        }
+    }
+
+    /// Take `bytes` tokens and return how long to wait before using them.
+    fn take(&mut self, bytes: u64) -> Duration {
+        self.take_at(bytes, Instant::now())
+    }
+
+    fn take_at(&mut self, bytes: u64, now: Instant) -> Duration {
+        let refill = now.duration_since(self.last).as_secs_f64() * self.rate as f64;
+        self.tokens = (self.tokens + refill).min(self.rate as f64);
+        self.last = now;
+        self.tokens -= bytes as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate as f64)
+        }
+    }
+}
+
+/// 配置中的限速时段，`from` 晚于 `to` 时跨越午夜。
+/// A time-of-day window with its own rate; it wraps past midnight when
+/// `from` is later than `to`.
+#[derive(Clone, Debug)]
+pub struct ScheduleEntry {
+    pub from: NaiveTime,
+    pub to: NaiveTime,
+    pub rate: Option<u64>,
+}
+
+impl ScheduleEntry {
+    /// Parse `HH:MM` bounds and a rate as accepted by [`parse_rate`].
+    pub fn parse(from: &str, to: &str, rate: &str) -> Result<Self> {
+        let time = |text: &str| {
+            NaiveTime::parse_from_str(text.trim(), "%H:%M")
+                .map_err(|_| anyhow!("Invalid time in bandwidth schedule: {} (HH:MM)", text))
+        };
+        Ok(Self {
+            from: time(from)?,
+            to: time(to)?,
+            rate: parse_rate(rate)?,
+        })
+    }
+
+    fn contains(&self, now: NaiveTime) -> bool {
+        if self.from <= self.to {
+            self.from <= now && now < self.to
+        } else {
+            now >= self.from || now < self.to
+        }
+    }
+}
+
+/// 会话级限速：`set bandwidth` 设置的速率与配置时段中较低者生效，所有传输共享一个令牌桶。
+/// The session-wide cap shared by every transfer: the lower of the rate set
+/// with `set bandwidth` and the one scheduled for the current time.
+struct SessionLimit {
+    manual: Mutex<Option<u64>>,
+    schedule: Vec<ScheduleEntry>,
+    bucket: Mutex<Option<TokenBucket>>,
+}
+
+impl SessionLimit {
+    fn rate(&self) -> Option<u64> {
+        let manual = *self.manual.lock().unwrap();
+        match (manual, scheduled_rate_in(&self.schedule)) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    fn take(&self, bytes: u64) -> Duration {
+        let rate = self.rate();
+        let mut bucket = self.bucket.lock().unwrap();
+        match rate {
+            None => {
+                *bucket = None;
+                Duration::ZERO
+            }
+            Some(rate) => {
+                let bucket = bucket.get_or_insert_with(|| TokenBucket::new(rate));
+                bucket.set_rate(rate);
+                bucket.take(bytes)
+            }
+        }
+    }
+}
+
+fn scheduled_rate_in(schedule: &[ScheduleEntry]) -> Option<u64> {
+    let now = Local::now().time().with_nanosecond(0).unwrap_or_default();
+    rate_at(schedule, now)
+}
+
+/// The rate of the first schedule entry covering `now`.
+fn rate_at(schedule: &[ScheduleEntry], now: NaiveTime) -> Option<u64> {
+    schedule
+        .iter()
+        .find(|entry| entry.contains(now))
+        .and_then(|entry| entry.rate)
+}
+
+fn session() -> &'static SessionLimit {
+    SESSION.get_or_init(|| SessionLimit {
+        manual: Mutex::new(None),
+        schedule: Vec::new(),
+        bucket: Mutex::new(None),
+    })
+}
+
+/// Install the schedule from the config file. Later calls are ignored.
+pub fn init(schedule: Vec<ScheduleEntry>) {
+    let _ = SESSION.set(SessionLimit {
+        manual: Mutex::new(None),
+        schedule,
+        bucket: Mutex::new(None),
+    });
+}
+
+/// Set or clear the session-wide cap (`set bandwidth`).
+pub fn set_session_rate(rate: Option<u64>) {
+    *session().manual.lock().unwrap() = rate;
+}
+
+/// The cap set with `set bandwidth`, if any.
+pub fn session_rate() -> Option<u64> {
+    *session().manual.lock().unwrap()
+}
+
+/// The rate the config schedule imposes right now, if any.
+pub fn scheduled_rate() -> Option<u64> {
+    scheduled_rate_in(&session().schedule)
+}
+
+/// 解析速率：`5M`、`512K`、`1048576`（每秒字节数）；`0`、`off`、`unlimited` 表示不限速。
+/// Parse a rate in bytes per second such as `5M` or `512K`. `0`, `off`,
+/// `none` and `unlimited` mean no limit.
+pub fn parse_rate(text: &str) -> Result<Option<u64>> {
+    let text = text.trim();
+    if ["off", "none", "unlimited"]
+        .iter()
+        .any(|word| text.eq_ignore_ascii_case(word))
+    {
+        return Ok(None);
+    }
+    let rate = parse_size(text.trim_end_matches("/s"))?;
+    Ok((rate > 0).then_some(rate))
+}
+
+/// 一次传输的限速器：自身的 `--limit-rate` 令牌桶加上会话级限速。
+/// Throttle for one transfer: its own `--limit-rate` bucket, if any, on top
+/// of the session-wide cap. Shared by every chunk or part of the transfer.
+pub struct Throttle {
+    own: Option<Mutex<TokenBucket>>,
+}
+
+impl Throttle {
+    pub fn new(limit: Option<u64>) -> Arc<Self> {
+        Arc::new(Self {
+            own: limit.map(|rate| Mutex::new(TokenBucket::new(rate))),
+        })
+    }
+
+    /// Wait until `bytes` may be transferred under every applicable limit.
+    pub async fn consume(&self, bytes: u64) {
+        let own = self
+            .own
+            .as_ref()
+            .map_or(Duration::ZERO, |bucket| bucket.lock().unwrap().take(bytes));
+        let wait = own.max(session().take(bytes));
+        if !wait.is_zero() {
+            sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(text: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(text, "%H:%M").unwrap()
+    }
+
+    #[test]
+    fn daytime_window() {
+        let entry = ScheduleEntry::parse("09:00", "17:00", "1M").unwrap();
+        assert!(entry.contains(time("09:00")));
+        assert!(entry.contains(time("16:59")));
+        assert!(!entry.contains(time("17:00")));
+        assert!(!entry.contains(time("08:59")));
+        assert!(!entry.contains(time("23:00")));
+    }
+
+    #[test]
+    fn window_wrapping_midnight() {
+        let entry = ScheduleEntry::parse("22:00", "06:00", "1M").unwrap();
+        assert!(entry.contains(time("22:00")));
+        assert!(entry.contains(time("23:59")));
+        assert!(entry.contains(time("00:00")));
+        assert!(entry.contains(time("05:59")));
+        assert!(!entry.contains(time("06:00")));
+        assert!(!entry.contains(time("12:00")));
+        assert!(!entry.contains(time("21:59")));
+    }
+
+    #[test]
+    fn empty_window() {
+        let entry = ScheduleEntry::parse("08:00", "08:00", "1M").unwrap();
+        assert!(!entry.contains(time("08:00")));
+        assert!(!entry.contains(time("12:00")));
+    }
+
+    #[test]
+    fn first_matching_entry_wins() {
+        let schedule = [
+            ScheduleEntry::parse("09:00", "17:00", "1M").unwrap(),
+            ScheduleEntry::parse("00:00", "23:59", "5M").unwrap(),
+            ScheduleEntry::parse("18:00", "20:00", "off").unwrap(),
+        ];
+        assert_eq!(rate_at(&schedule, time("10:00")), Some(1 << 20));
+        assert_eq!(rate_at(&schedule, time("08:00")), Some(5 << 20));
+        assert_eq!(rate_at(&schedule[2..], time("19:00")), None);
+        assert_eq!(rate_at(&schedule[2..], time("21:00")), None);
+    }
+
+    #[test]
+    fn schedule_entry_errors() {
+        assert!(ScheduleEntry::parse("25:00", "06:00", "1M").is_err());
+        assert!(ScheduleEntry::parse("9", "17:00", "1M").is_err());
+        assert!(ScheduleEntry::parse("09:00", "17:00", "fast").is_err());
+        assert!(ScheduleEntry::parse(" 09:00 ", "17:00", "1M").is_ok());
+    }
+
+    #[test]
+    fn rates() {
+        for off in ["off", "OFF", "none", "unlimited", "0", "0K", " off "] {
+            assert_eq!(parse_rate(off).unwrap(), None, "{:?}", off);
+        }
+        assert_eq!(parse_rate("5M/s").unwrap(), Some(5 << 20));
+        assert_eq!(parse_rate("5M").unwrap(), Some(5 << 20));
+        assert_eq!(parse_rate("512K").unwrap(), Some(512 << 10));
+        assert_eq!(parse_rate("1048576").unwrap(), Some(1 << 20));
+        for bad in ["", "fast", "-1M", "5X"] {
+            assert!(parse_rate(bad).is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn debt_turns_into_wait() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(100);
+        bucket.last = start;
+        assert_eq!(bucket.take_at(50, start), Duration::ZERO);
+        assert_eq!(bucket.take_at(100, start), Duration::from_millis(500));
+        // Half a second later the debt is repaid.
+        let later = start + Duration::from_millis(500);
+        assert_eq!(bucket.take_at(0, later), Duration::ZERO);
+        assert_eq!(bucket.take_at(25, later), Duration::from_millis(250));
+    }
+
+    #[test]
+    fn bucket_holds_at_most_one_second() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(100);
+        bucket.last = start;
+        let later = start + Duration::from_secs(10);
+        assert_eq!(bucket.take_at(100, later), Duration::ZERO);
+        assert_eq!(bucket.take_at(10, later), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn lower_rate_caps_saved_tokens() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(1000);
+        bucket.last = start;
+        bucket.set_rate(100);
+        assert_eq!(bucket.take_at(100, start), Duration::ZERO);
+        assert_eq!(bucket.take_at(50, start), Duration::from_millis(500));
+    }
+}
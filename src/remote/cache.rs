@@ -0,0 +1,110 @@
+use anyhow::Result;
+use reqwest::Client;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex as AsyncMutex;
+
+use super::ls::{list_all_items, FileItem};
+
+/// How long a folder listing is trusted before it is fetched again.
+const LISTING_TTL: Duration = Duration::from_secs(60);
+
+struct CachedListing {
+    items: Arc<Vec<FileItem>>,
+    fetched_at: Instant,
+}
+
+/// Per-folder listing cache shared by command execution and tab completion.
+/// Listings are fetched through one HTTP client and fully paginated; at most
+/// one fetch per folder runs at a time, and concurrent misses wait for it.
+/// 目录列表缓存：命令执行与 TAB 补全共用，按文件夹 ID 缓存完整（已翻页）的列表；
+/// 同一文件夹同时只有一个请求，并发的未命中会等待它完成。
+#[derive(Clone)]
+pub struct ListingCache {
+    client: Client,
+    folders: Arc<Mutex<HashMap<String, CachedListing>>>,
+    fetching: Arc<Mutex<HashMap<String, Arc<AsyncMutex<()>>>>>,
+}
+
+impl ListingCache {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            folders: Arc::new(Mutex::new(HashMap::new())),
+            fetching: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Return the cached listing if it is still fresh, without any I/O.
+    /// 仅读取缓存（未过期时返回），不发起网络请求。
+    pub fn peek(&self, folder_id: &str) -> Option<Arc<Vec<FileItem>>> {
+        let folders = self.folders.lock().unwrap();
+        folders
+            .get(folder_id)
+            .filter(|cached| cached.fetched_at.elapsed() < LISTING_TTL)
+            .map(|cached| Arc::clone(&cached.items))
+    }
+
+    /// Return the listing of a folder, fetching it on a cache miss.
+    /// 获取文件夹列表，缓存未命中时请求接口。
+    pub async fn items(
+        &self,
+        token: &str,
+        drive_id: &str,
+        folder_id: &str,
+    ) -> Result<Arc<Vec<FileItem>>> {
+        if let Some(items) = self.peek(folder_id) {
+            return Ok(items);
+        }
+        let lock = self.fetch_lock(folder_id);
+        let _fetching = lock.lock().await;
+        // 等待期间其他请求可能已拉取完成
+        if let Some(items) = self.peek(folder_id) {
+            return Ok(items);
+        }
+        self.fetch(token, drive_id, folder_id).await
+    }
+
+    /// Fetch a folder listing unconditionally and store it.
+    /// 强制重新拉取文件夹列表并写入缓存。
+    pub async fn refresh(
+        &self,
+        token: &str,
+        drive_id: &str,
+        folder_id: &str,
+    ) -> Result<Arc<Vec<FileItem>>> {
+        let lock = self.fetch_lock(folder_id);
+        let _fetching = lock.lock().await;
+        self.fetch(token, drive_id, folder_id).await
+    }
+
+    /// The lock serialising fetches of one folder.
+    fn fetch_lock(&self, folder_id: &str) -> Arc<AsyncMutex<()>> {
+        let mut fetching = self.fetching.lock().unwrap();
+        Arc::clone(fetching.entry(folder_id.to_string()).or_default())
+    }
+
+    async fn fetch(
+        &self,
+        token: &str,
+        drive_id: &str,
+        folder_id: &str,
+    ) -> Result<Arc<Vec<FileItem>>> {
+        let items = Arc::new(list_all_items(&self.client, token, drive_id, folder_id).await?);
+        self.folders.lock().unwrap().insert(
+            folder_id.to_string(),
+            CachedListing {
+                items: Arc::clone(&items),
+                fetched_at: Instant::now(),
+            },
+        );
+        Ok(items)
+    }
+
+    /// Drop a folder's listing after its contents changed.
+    /// 目录内容变化后（上传、删除、移动等）使其缓存失效。
+    pub fn invalidate(&self, folder_id: &str) {
+        self.folders.lock().unwrap().remove(folder_id);
+    }
+}
@@ -0,0 +1,447 @@
+use anyhow::{anyhow, Result};
+use futures::StreamExt;
+use reqwest::{header, Client, StatusCode};
+use std::collections::VecDeque;
+use std::io::{ErrorKind, Write};
+
+use super::filter::parse_size;
+use super::get::request_download_url;
+use super::ls::FileItem;
+
+/// Size of the first window `tail -n` fetches from the end of the file; it
+/// grows until enough lines are found.
+const TAIL_WINDOW: u64 = 64 * 1024;
+
+/// Most of the file `tail -n` buffers while searching backwards; beyond it the
+/// start of the output is located with a streaming pass instead.
+const TAIL_MAX_BUFFER: u64 = 64 * 1024 * 1024;
+
+/// 要输出的文件片段。
+/// The part of a remote file to print.
+#[derive(Clone, Copy, Debug)]
+pub enum Portion {
+    All,
+    /// Inclusive byte range; `None` as the end means to the end of the file.
+    Range(u64, Option<u64>),
+    FirstBytes(u64),
+    /// Last `n` bytes.
+    LastBytes(u64),
+    FirstLines(u64),
+    LastLines(u64),
+}
+
+impl Portion {
+    /// Parse `start-end`, `start-` or `-count` (the last `count` bytes).
+    /// Both ends are inclusive and accept sizes such as `1M`.
+    pub fn parse_range(text: &str) -> Result<Self> {
+        let invalid = || anyhow!("Invalid range: {} (e.g. 0-1023, 1M-, -512)", text);
+        let (start, end) = text.split_once('-').ok_or_else(invalid)?;
+        match (start.trim(), end.trim()) {
+            ("", "") => Err(invalid()),
+            ("", count) => Ok(Portion::LastBytes(parse_size(count)?)),
+            (start, "") => Ok(Portion::Range(parse_size(start)?, None)),
+            (start, end) => {
+                let (start, end) = (parse_size(start)?, parse_size(end)?);
+                if end < start {
+                    return Err(invalid());
+                }
+                Ok(Portion::Range(start, Some(end)))
+            }
+        }
+    }
+}
+
+/// 以流的方式将远程文件（或其中一段）按顺序输出到标准输出，只请求所需的字节范围。
+/// Stream a remote file, or part of it, to stdout in order. Only the bytes
+/// needed are requested, so reading the head of a huge file is cheap.
+pub async fn cat(token: &str, drive_id: &str, item: &FileItem, portion: Portion) -> Result<()> {
+    let client = Client::new();
+    let url = request_download_url(&client, token, drive_id, &item.file_id)
+        .await?
+        .url;
+    let size = item.size.unwrap_or(0);
+    if size == 0 {
+        return Ok(());
+    }
+    let last = size - 1;
+    let mut out = std::io::stdout().lock();
+
+    let result = match portion {
+        Portion::All => stream_range(&client, &url, 0, last, |bytes| write(&mut out, bytes)).await,
+        Portion::Range(start, end) => {
+            if start > last {
+                return Err(anyhow!(
+                    "Range starts past the end of the file ({} bytes)",
+                    size
+                ));
+            }
+            let end = end.unwrap_or(last).min(last);
+            stream_range(&client, &url, start, end, |bytes| write(&mut out, bytes)).await
+        }
+        Portion::FirstBytes(0)
+        | Portion::LastBytes(0)
+        | Portion::FirstLines(0)
+        | Portion::LastLines(0) => Ok(()),
+        Portion::FirstBytes(count) => {
+            let end = (count - 1).min(last);
+            stream_range(&client, &url, 0, end, |bytes| write(&mut out, bytes)).await
+        }
+        Portion::LastBytes(count) => {
+            let start = size.saturating_sub(count);
+            stream_range(&client, &url, start, last, |bytes| write(&mut out, bytes)).await
+        }
+        Portion::FirstLines(count) => {
+            let mut remaining = count;
+            stream_range(&client, &url, 0, last, |bytes| {
+                let mut end = bytes.len();
+                for (i, _) in bytes.iter().enumerate().filter(|(_, b)| **b == b'\n') {
+                    remaining -= 1;
+                    if remaining == 0 {
+                        end = i + 1;
+                        break;
+                    }
+                }
+                Ok(write(&mut out, &bytes[..end])? && remaining > 0)
+            })
+            .await
+        }
+        Portion::LastLines(count) => tail_lines(&client, &url, size, count, &mut out).await,
+    };
+    let _ = out.flush();
+    result
+}
+
+/// Request bytes `start..=end` and feed the body to `sink` as it arrives,
+/// until the range is done or `sink` returns `false`. Servers that ignore
+/// `Range` and answer with the whole file are handled by skipping ahead. A
+/// body that ends early (e.g. a dropped connection) is an error.
+async fn stream_range(
+    client: &Client,
+    url: &str,
+    start: u64,
+    end: u64,
+    mut sink: impl FnMut(&[u8]) -> Result<bool>,
+) -> Result<()> {
+    let resp = client
+        .get(url)
+        .header(header::RANGE, format!("bytes={}-{}", start, end))
+        .send()
+        .await?;
+    let mut skip = match resp.status() {
+        StatusCode::PARTIAL_CONTENT => 0,
+        StatusCode::OK => start,
+        status => return Err(anyhow!("Download Error: {}", status)),
+    };
+    let mut remaining = end - start + 1;
+    let mut body = resp.bytes_stream();
+    while let Some(bytes) = body.next().await {
+        let mut bytes = &bytes?[..];
+        if skip > 0 {
+            let n = skip.min(bytes.len() as u64);
+            skip -= n;
+            bytes = &bytes[n as usize..];
+        }
+        let take = remaining.min(bytes.len() as u64);
+        remaining -= take;
+        if !sink(&bytes[..take as usize])? || remaining == 0 {
+            return Ok(());
+        }
+    }
+    if remaining > 0 {
+        return Err(anyhow!(
+            "Download ended early: {} of {} bytes missing",
+            remaining,
+            end - start + 1
+        ));
+    }
+    Ok(())
+}
+
+/// Print the last `count` lines of a `size`-byte file. Growing windows are
+/// fetched from the end, each round requesting only the bytes in front of
+/// what is already buffered; past `TAIL_MAX_BUFFER` the remaining lines are
+/// located with a streaming pass so memory stays bounded.
+async fn tail_lines(
+    client: &Client,
+    url: &str,
+    size: u64,
+    count: u64,
+    out: &mut impl Write,
+) -> Result<()> {
+    let mut search = TailSearch::new(size, count, TAIL_WINDOW, TAIL_MAX_BUFFER);
+    // Buffered segments as (file offset, bytes), nearest the end first.
+    let mut segments: Vec<(u64, Vec<u8>)> = Vec::new();
+    while let Some((from, to)) = search.next_range() {
+        let mut segment = Vec::with_capacity((to - from) as usize);
+        stream_range(client, url, from, to - 1, |bytes| {
+            segment.extend_from_slice(bytes);
+            Ok(true)
+        })
+        .await?;
+        search.feed(&segment);
+        segments.push((from, segment));
+    }
+
+    let start = search.start;
+    let offset = match search.offset {
+        Some(offset) => offset,
+        None => {
+            let mut starts = LineStarts::new(search.remaining());
+            stream_range(client, url, 0, start - 1, |bytes| {
+                starts.feed(bytes);
+                Ok(true)
+            })
+            .await?;
+            starts.offset()
+        }
+    };
+    if offset < start {
+        let mut open = true;
+        stream_range(client, url, offset, start - 1, |bytes| {
+            open = write(out, bytes)?;
+            Ok(open)
+        })
+        .await?;
+        if !open {
+            return Ok(());
+        }
+    }
+    for (from, segment) in segments.iter().rev() {
+        let skip = offset.saturating_sub(*from) as usize;
+        if skip < segment.len() && !write(out, &segment[skip..])? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Backward search for the offset where the last `count` lines of a
+/// `size`-byte file begin. Segments are fed from the end towards the start;
+/// the search itself does no I/O.
+struct TailSearch {
+    size: u64,
+    count: u64,
+    window: u64,
+    max_buffer: u64,
+    /// Offset of the earliest byte fed so far.
+    start: u64,
+    /// Newlines seen so far, not counting the one that ends the file.
+    seen: u64,
+    offset: Option<u64>,
+}
+
+impl TailSearch {
+    /// `count` must be at least 1.
+    fn new(size: u64, count: u64, window: u64, max_buffer: u64) -> Self {
+        Self {
+            size,
+            count,
+            window,
+            max_buffer,
+            start: size,
+            seen: 0,
+            offset: None,
+        }
+    }
+
+    /// The range `from..to` to fetch next, or `None` once the offset is known
+    /// or `max_buffer` bytes have been fed.
+    fn next_range(&self) -> Option<(u64, u64)> {
+        let buffered = self.size - self.start;
+        if self.offset.is_some() || buffered >= self.max_buffer {
+            return None;
+        }
+        let len = self.window.min(self.max_buffer - buffered);
+        Some((self.start.saturating_sub(len), self.start))
+    }
+
+    /// Feed the bytes of the range returned by `next_range`.
+    fn feed(&mut self, segment: &[u8]) {
+        let from = self.start - segment.len() as u64;
+        // A trailing newline ends the last line rather than starting a new one.
+        let body = if self.start == self.size {
+            segment.strip_suffix(b"\n").unwrap_or(segment)
+        } else {
+            segment
+        };
+        for (i, byte) in body.iter().enumerate().rev() {
+            if *byte == b'\n' {
+                self.seen += 1;
+                if self.seen == self.count {
+                    self.offset = Some(from + i as u64 + 1);
+                    break;
+                }
+            }
+        }
+        if from == 0 && self.offset.is_none() {
+            self.offset = Some(0);
+        }
+        self.start = from;
+        self.window = self.window.saturating_mul(4);
+    }
+
+    /// Lines still to be found in front of `start` when the search stopped
+    /// at `max_buffer`.
+    fn remaining(&self) -> u64 {
+        self.count - self.seen
+    }
+}
+
+/// Forward pass over the start of a file that remembers only where the
+/// latest `count` lines begin.
+struct LineStarts {
+    count: u64,
+    pos: u64,
+    starts: VecDeque<u64>,
+}
+
+impl LineStarts {
+    fn new(count: u64) -> Self {
+        Self {
+            count,
+            pos: 0,
+            starts: VecDeque::new(),
+        }
+    }
+
+    fn feed(&mut self, bytes: &[u8]) {
+        for (i, _) in bytes.iter().enumerate().filter(|(_, b)| **b == b'\n') {
+            if self.starts.len() as u64 == self.count {
+                self.starts.pop_front();
+            }
+            self.starts.push_back(self.pos + i as u64 + 1);
+        }
+        self.pos += bytes.len() as u64;
+    }
+
+    /// Offset where the last `count` lines of the bytes fed so far begin.
+    fn offset(&self) -> u64 {
+        if self.starts.len() as u64 == self.count {
+            self.starts[0]
+        } else {
+            0
+        }
+    }
+}
+
+/// Write to stdout, reporting a closed pipe (e.g. `| head`) as "stop".
+fn write(out: &mut impl Write, bytes: &[u8]) -> Result<bool> {
+    match out.write_all(bytes).and_then(|_| out.flush()) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run the tail search over an in-memory file, falling back to the
+    /// forward pass exactly as `tail_lines` does.
+    fn tail(file: &[u8], count: u64, window: u64, max_buffer: u64) -> &[u8] {
+        let mut search = TailSearch::new(file.len() as u64, count, window, max_buffer);
+        while let Some((from, to)) = search.next_range() {
+            search.feed(&file[from as usize..to as usize]);
+        }
+        let offset = search.offset.unwrap_or_else(|| {
+            let mut starts = LineStarts::new(search.remaining());
+            starts.feed(&file[..search.start as usize]);
+            starts.offset()
+        });
+        &file[offset as usize..]
+    }
+
+    /// The last `count` lines, computed the obvious way.
+    fn expected(file: &[u8], count: u64) -> Vec<u8> {
+        let lines: Vec<&[u8]> = file.split_inclusive(|b| *b == b'\n').collect();
+        let skip = lines.len().saturating_sub(count as usize);
+        lines[skip..].concat()
+    }
+
+    #[test]
+    fn final_newline() {
+        assert_eq!(tail(b"a\nb\nc\n", 2, 64, 1024), b"b\nc\n");
+        assert_eq!(tail(b"a\nb\nc", 2, 64, 1024), b"b\nc");
+        assert_eq!(tail(b"a\nb\nc\n", 1, 64, 1024), b"c\n");
+        assert_eq!(tail(b"\n\n", 1, 64, 1024), b"\n");
+    }
+
+    #[test]
+    fn count_beyond_line_count() {
+        assert_eq!(tail(b"a\nb\n", 10, 64, 1024), b"a\nb\n");
+        assert_eq!(tail(b"no newline", 3, 4, 1024), b"no newline");
+    }
+
+    #[test]
+    fn lines_spanning_windows() {
+        let file = b"first line\nsecond\n\nfourth, a longer one\nlast\n";
+        for count in 1..=7 {
+            for window in 1..=file.len() as u64 {
+                assert_eq!(
+                    tail(file, count, window, u64::MAX),
+                    expected(file, count),
+                    "count {} window {}",
+                    count,
+                    window
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn streaming_fallback_past_buffer_limit() {
+        for file in [
+            &b"a\nbb\nccc\ndddd\neeeee\n"[..],
+            b"a\nbb\nccc\ndddd\neeeee",
+        ] {
+            for count in 1..=6 {
+                for max_buffer in 1..=file.len() as u64 {
+                    assert_eq!(
+                        tail(file, count, 2, max_buffer),
+                        expected(file, count),
+                        "count {} max_buffer {}",
+                        count,
+                        max_buffer
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn window_never_exceeds_buffer_limit() {
+        let mut search = TailSearch::new(1000, 1, 64, 100);
+        let mut buffered = 0;
+        while let Some((from, to)) = search.next_range() {
+            buffered += to - from;
+            search.feed(&vec![b'x'; (to - from) as usize]);
+        }
+        assert_eq!(buffered, 100);
+        assert_eq!(search.offset, None);
+        assert_eq!(search.start, 900);
+    }
+
+    #[test]
+    fn parse_ranges() {
+        assert!(matches!(
+            Portion::parse_range("0-1023").unwrap(),
+            Portion::Range(0, Some(1023))
+        ));
+        assert!(matches!(
+            Portion::parse_range("1M-").unwrap(),
+            Portion::Range(1048576, None)
+        ));
+        assert!(matches!(
+            Portion::parse_range("-512").unwrap(),
+            Portion::LastBytes(512)
+        ));
+        assert!(matches!(
+            Portion::parse_range(" 5 - 5 ").unwrap(),
+            Portion::Range(5, Some(5))
+        ));
+        for bad in ["", "-", "512", "5-3", "a-", "-x", "1-2-3"] {
+            assert!(Portion::parse_range(bad).is_err(), "{:?}", bad);
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use anyhow::{anyhow, Result};
+use indicatif::ProgressBar;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+
+/// 默认并发连接数（普通应用限制，所有文件共享）。
+/// Connections used when neither a flag nor the config says otherwise.
+pub const DEFAULT_CONNECTIONS: usize = 3;
+/// Starting point and ceiling of the adaptive mode.
+const ADAPTIVE_START: usize = 2;
+const ADAPTIVE_MAX: usize = 16;
+/// How often the adaptive mode samples throughput.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(3);
+/// Throughput must beat the previous sample by this factor to grow again.
+const IMPROVEMENT: f64 = 1.1;
+
+/// 下载并发方式：固定连接数，或根据吞吐量自动调整。
+/// How many connections a download may use: a fixed number, or `auto`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Connections {
+    Fixed(usize),
+    Adaptive,
+}
+
+impl Connections {
+    /// Parse a positive number or `auto`.
+    pub fn parse(text: &str) -> Result<Self> {
+        if text.eq_ignore_ascii_case("auto") {
+            return Ok(Connections::Adaptive);
+        }
+        match text.parse() {
+            Ok(n) if n > 0 => Ok(Connections::Fixed(n)),
+            _ => Err(anyhow!("Invalid connections: {} (a number or auto)", text)),
+        }
+    }
+}
+
+impl Default for Connections {
+    fn default() -> Self {
+        Connections::Fixed(DEFAULT_CONNECTIONS)
+    }
+}
+
+/// 所有分片共享的连接额度。自适应模式下吞吐量提升时增加连接，遇到并发限制 403 时减半。
+/// The connection budget shared by every chunk of a batch. In adaptive mode
+/// it grows by one while throughput keeps improving and halves when the
+/// server answers with a concurrency 403.
+pub struct Limiter {
+    semaphore: Arc<Semaphore>,
+    /// Permits to take out of circulation after a back-off. They are retired
+    /// as soon as they are acquired or returned, ahead of queued chunks.
+    retire: Arc<AtomicUsize>,
+    current: AtomicUsize,
+    max: usize,
+    adaptive: bool,
+    last_backoff: Mutex<Option<Instant>>,
+}
+
+impl Limiter {
+    pub fn new(connections: Connections) -> Arc<Self> {
+        let (start, max, adaptive) = match connections {
+            Connections::Fixed(n) => (n, n, false),
+            Connections::Adaptive => (ADAPTIVE_START, ADAPTIVE_MAX, true),
+        };
+        Arc::new(Self {
+            semaphore: Arc::new(Semaphore::new(start)),
+            retire: Arc::new(AtomicUsize::new(0)),
+            current: AtomicUsize::new(start),
+            max,
+            adaptive,
+            last_backoff: Mutex::new(None),
+        })
+    }
+
+    /// Upper bound on connections, for sizing task buffers.
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Connections currently allowed.
+    pub fn current(&self) -> usize {
+        self.current.load(Ordering::SeqCst)
+    }
+
+    pub async fn acquire(&self) -> Result<Permit> {
+        loop {
+            let permit = Arc::clone(&self.semaphore).acquire_owned().await?;
+            if !take_one(&self.retire) {
+                return Ok(Permit {
+                    permit: Some(permit),
+                    retire: Arc::clone(&self.retire),
+                });
+            }
+            permit.forget();
+        }
+    }
+
+    /// React to a concurrency 403. Several chunks usually hit the limit
+    /// together, so only one back-off happens per sampling interval.
+    pub fn back_off(&self) {
+        if !self.adaptive {
+            return;
+        }
+        let mut last = self.last_backoff.lock().unwrap();
+        if last.is_some_and(|t| t.elapsed() < SAMPLE_INTERVAL) {
+            return;
+        }
+        *last = Some(Instant::now());
+        let current = self.current();
+        let target = (current / 2).max(1);
+        self.current.store(target, Ordering::SeqCst);
+        let mut excess = current - target;
+        // Idle permits can be removed at once; the rest retire as they return.
+        while excess > 0 {
+            match Arc::clone(&self.semaphore).try_acquire_owned() {
+                Ok(permit) => permit.forget(),
+                Err(_) => break,
+            }
+            excess -= 1;
+        }
+        self.retire.fetch_add(excess, Ordering::SeqCst);
+    }
+
+    fn grow(&self) {
+        let current = self.current();
+        if current < self.max {
+            self.current.store(current + 1, Ordering::SeqCst);
+            // Cancel a pending retirement before minting a new permit.
+            if !take_one(&self.retire) {
+                self.semaphore.add_permits(1);
+            }
+        }
+    }
+
+    /// In adaptive mode, sample the progress bar's throughput and add a
+    /// connection whenever it improved. Abort the handle when the batch ends.
+    pub fn spawn_tuner(self: &Arc<Self>, progress: ProgressBar) -> Option<JoinHandle<()>> {
+        if !self.adaptive {
+            return None;
+        }
+        let limiter = Arc::clone(self);
+        Some(tokio::spawn(async move {
+            let mut last_position = progress.position();
+            let mut last_rate = 0.0;
+            loop {
+                sleep(SAMPLE_INTERVAL).await;
+                let position = progress.position();
+                let rate = position.saturating_sub(last_position) as f64;
+                last_position = position;
+                let backed_off = limiter
+                    .last_backoff
+                    .lock()
+                    .unwrap()
+                    .is_some_and(|t| t.elapsed() < SAMPLE_INTERVAL * 2);
+                if !backed_off && rate > last_rate * IMPROVEMENT {
+                    limiter.grow();
+                }
+                last_rate = rate;
+            }
+        }))
+    }
+}
+
+/// 连接额度；归还时若有待回收的额度则直接作废。
+/// A connection slot. When dropped it goes back to the pool, unless a
+/// back-off is waiting for slots to retire.
+pub struct Permit {
+    permit: Option<OwnedSemaphorePermit>,
+    retire: Arc<AtomicUsize>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Some(permit) = self.permit.take() {
+            if take_one(&self.retire) {
+                permit.forget();
+            }
+        }
+    }
+}
+
+/// Decrement `counter` if it is non-zero; returns whether it did.
+fn take_one(counter: &AtomicUsize) -> bool {
+    counter
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+        .is_ok()
+}
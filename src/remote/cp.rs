@@ -1,4 +1,3 @@
-use crate::remote::ls::find_file_id_by_name;
 use anyhow::Result;
 use reqwest::Client;
 use serde_json::json;
@@ -8,12 +7,11 @@ use serde_json::json;
 pub async fn copy_file(
     token: &str,
     drive_id: &str,
-    parent_file_id: &str,
+    src_file_id: &str,
     src_name: &str,
     to_parent_file_id: &str,
     new_name: &str,
 ) -> Result<()> {
-    let src_file_id = find_file_id_by_name(token, drive_id, parent_file_id, src_name).await?;
     let client = Client::new();
 
     let url = "https://openapi.alipan.com/adrive/v1.0/openFile/copy";
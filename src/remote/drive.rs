@@ -1,7 +1,12 @@
 use anyhow::Result;
+use chrono::{DateTime, Local};
+use colored::Colorize;
 use reqwest::Client;
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 
+use super::output::human_size;
+
 #[derive(Deserialize)]
 struct DriveInfo {
     default_drive_id: String,
@@ -27,3 +32,100 @@ pub async fn get_drive_id(token: &str) -> Result<String> {
     let info: DriveInfo = res.json().await?;
     Ok(info.default_drive_id)
 }
+
+#[derive(Deserialize, Debug)]
+struct SpaceInfoResponse {
+    personal_space_info: SpaceInfo,
+}
+
+/// Capacity of the user's personal space, in bytes.
+#[derive(Deserialize, Debug)]
+pub struct SpaceInfo {
+    pub total_size: u64,
+    pub used_size: u64,
+}
+
+impl SpaceInfo {
+    pub fn free_size(&self) -> u64 {
+        self.total_size.saturating_sub(self.used_size)
+    }
+}
+
+/// Membership tier returned by `getVipInfo`.
+#[derive(Deserialize, Debug)]
+pub struct VipInfo {
+    /// `member`, `vip` or `svip`.
+    pub identity: String,
+    pub level: Option<String>,
+    /// Expiry as a Unix timestamp in seconds.
+    pub expire: Option<i64>,
+}
+
+/// POST an empty body to a `user/*` endpoint and decode the response.
+async fn user_api<T: DeserializeOwned>(token: &str, endpoint: &str, what: &str) -> Result<T> {
+    let url = format!("https://openapi.alipan.com/adrive/v1.0/user/{}", endpoint);
+    let res = Client::new()
+        .post(url)
+        .bearer_auth(token)
+        .json(&serde_json::json!({}))
+        .send()
+        .await?;
+
+    if !res.status().is_success() {
+        let text = res.text().await?;
+        anyhow::bail!("Failed to get {}: {}", what, text);
+    }
+    Ok(res.json().await?)
+}
+
+/// 获取空间容量信息（总量 / 已用）。
+/// Fetch the total and used size of the personal space.
+pub async fn get_space_info(token: &str) -> Result<SpaceInfo> {
+    let resp: SpaceInfoResponse = user_api(token, "getSpaceInfo", "space info").await?;
+    Ok(resp.personal_space_info)
+}
+
+/// 获取会员信息（等级与到期时间）。
+/// Fetch the membership tier and its expiry.
+pub async fn get_vip_info(token: &str) -> Result<VipInfo> {
+    user_api(token, "getVipInfo", "VIP info").await
+}
+
+/// 显示空间用量与会员信息。
+/// Print total, used and free space followed by the membership tier.
+pub async fn print_disk_free(token: &str) -> Result<()> {
+    let (space, vip) = tokio::try_join!(get_space_info(token), get_vip_info(token))?;
+
+    let percent = if space.total_size == 0 {
+        0.0
+    } else {
+        space.used_size as f64 * 100.0 / space.total_size as f64
+    };
+    let rows = [
+        ("Total", space.total_size, String::new()),
+        ("Used", space.used_size, format!(" ({:.1}%)", percent)),
+        ("Free", space.free_size(), String::new()),
+    ];
+    for (label, bytes, note) in rows {
+        println!(
+            "{:>10}: {:>8}{}  {}",
+            label.bold(),
+            human_size(bytes),
+            note,
+            format!("{} bytes", bytes).dimmed()
+        );
+    }
+
+    let mut tier = vip.identity.clone();
+    if let Some(level) = vip.level.as_deref().filter(|l| !l.is_empty()) {
+        tier = format!("{} ({})", tier, level);
+    }
+    println!("{:>10}: {}", "Membership".bold(), tier);
+    if let Some(expire) = vip.expire.filter(|e| *e > 0) {
+        let expiry = DateTime::from_timestamp(expire, 0)
+            .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| expire.to_string());
+        println!("{:>10}: {}", "Expires".bold(), expiry);
+    }
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use anyhow::Result;
+use colored::Colorize;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::time::Duration;
+
+use super::ls::ListOptions;
+use super::output::human_size;
+use super::walk::{walk, Listings};
+
+#[derive(Clone, Debug, Default)]
+pub struct DuOptions {
+    /// Print only the total of the target (`-s`).
+    pub summarize: bool,
+    /// Deepest folder level to report (`-d`); the target itself is level 0.
+    pub max_depth: Option<usize>,
+    /// Print sizes as `1.2G` instead of bytes (`-h`).
+    pub human: bool,
+}
+
+struct FolderUsage {
+    path: String,
+    depth: usize,
+    bytes: u64,
+}
+
+/// 统计文件夹占用空间：递归汇总所有文件大小，按大小降序输出各文件夹合计。
+/// Sum file sizes under `root_id` recursively and print per-folder totals,
+/// largest first.
+pub async fn print_disk_usage(
+    token: &str,
+    drive_id: &str,
+    root_id: &str,
+    root_label: &str,
+    options: &DuOptions,
+) -> Result<()> {
+    let pb = ProgressBar::new_spinner();
+    pb.set_style(
+        ProgressStyle::default_spinner()
+            .template("{spinner:.green} Scanning... {pos} folders [{elapsed_precise}]")
+            .unwrap(),
+    );
+    pb.enable_steady_tick(Duration::from_millis(120));
+    let listings = walk(
+        token,
+        drive_id,
+        root_id,
+        &ListOptions::default(),
+        None,
+        Some(&pb),
+    )
+    .await;
+    pb.finish_and_clear();
+    let listings = listings?;
+
+    let mut folders = Vec::new();
+    folder_usage(&listings, root_id, root_label, 0, &mut folders);
+
+    let max_depth = if options.summarize {
+        Some(0)
+    } else {
+        options.max_depth
+    };
+    let mut rows: Vec<&FolderUsage> = folders
+        .iter()
+        .filter(|f| max_depth.is_none_or(|max| f.depth <= max))
+        .collect();
+    rows.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+
+    for folder in rows {
+        let size = if options.human {
+            human_size(folder.bytes)
+        } else {
+            folder.bytes.to_string()
+        };
+        println!("{:>12}  {}", size, folder.path.blue());
+    }
+    Ok(())
+}
+
+/// Total size of `folder_id`, recording it and every subfolder's total.
+fn folder_usage(
+    listings: &Listings,
+    folder_id: &str,
+    path: &str,
+    depth: usize,
+    folders: &mut Vec<FolderUsage>,
+) -> u64 {
+    let mut bytes = 0;
+    for item in listings.get(folder_id).into_iter().flatten() {
+        if item.kind == "folder" {
+            let child = format!("{}/{}", path.trim_end_matches('/'), item.name);
+            bytes += folder_usage(listings, &item.file_id, &child, depth + 1, folders);
+        } else {
+            bytes += item.size.unwrap_or(0);
+        }
+    }
+    folders.push(FolderUsage {
+        path: path.to_string(),
+        depth,
+        bytes,
+    });
+    bytes
+}
@@ -0,0 +1,212 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use super::ls::FileItem;
+
+/// Values accepted by the list endpoint's `category` filter.
+pub const CATEGORIES: &[&str] = &["video", "image", "doc", "audio", "zip", "others"];
+
+/// Filters the list endpoint cannot apply, checked on each page as it
+/// arrives. Extension and size filters only ever match files.
+/// 接口不支持的过滤条件，在翻页过程中于本地逐页应用；扩展名与大小条件只匹配文件。
+#[derive(Clone, Debug, Default)]
+pub struct ItemFilter {
+    /// Lower-case extensions without the dot.
+    pub extensions: Vec<String>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    pub newer: Option<DateTime<Utc>>,
+    pub older: Option<DateTime<Utc>>,
+}
+
+impl ItemFilter {
+    pub fn matches(&self, item: &FileItem) -> bool {
+        let is_file = item.kind != "folder";
+        if !self.extensions.is_empty() {
+            let ext = item
+                .file_extension
+                .clone()
+                .or_else(|| item.name.rsplit_once('.').map(|(_, ext)| ext.to_string()))
+                .map(|ext| ext.to_lowercase());
+            if !is_file || !ext.is_some_and(|ext| self.extensions.contains(&ext)) {
+                return false;
+            }
+        }
+        if self.min_size.is_some() || self.max_size.is_some() {
+            let size = item.size.unwrap_or(0);
+            if !is_file
+                || self.min_size.is_some_and(|min| size < min)
+                || self.max_size.is_some_and(|max| size > max)
+            {
+                return false;
+            }
+        }
+        if self.newer.is_some() || self.older.is_some() {
+            let Some(updated) = item
+                .updated_at
+                .as_deref()
+                .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            else {
+                return false;
+            };
+            if self.newer.is_some_and(|newer| updated <= newer)
+                || self.older.is_some_and(|older| updated >= older)
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Parse a comma-separated extension list such as `mp4,.MKV`.
+    pub fn parse_extensions(list: &str) -> Vec<String> {
+        list.split(',')
+            .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+            .filter(|ext| !ext.is_empty())
+            .collect()
+    }
+}
+
+/// 解析大小，如 `512`、`10K`、`1.5G`（1024 进制）。
+/// Parse a size such as `512`, `10K` or `1.5G` (powers of 1024).
+pub fn parse_size(text: &str) -> Result<u64> {
+    let text = text.trim();
+    let upper = text.to_ascii_uppercase();
+    let number = upper.trim_end_matches(['B', 'I']);
+    let (digits, shift) = match number.chars().last() {
+        Some('K') => (&number[..number.len() - 1], 10),
+        Some('M') => (&number[..number.len() - 1], 20),
+        Some('G') => (&number[..number.len() - 1], 30),
+        Some('T') => (&number[..number.len() - 1], 40),
+        _ => (number, 0),
+    };
+    let value: f64 = digits
+        .parse()
+        .ok()
+        .filter(|v: &f64| *v >= 0.0)
+        .ok_or_else(|| anyhow!("Invalid size: {}", text))?;
+    Ok((value * (1u64 << shift) as f64) as u64)
+}
+
+/// 解析时间：`2024-05-01`、`2024-05-01 12:00`、RFC 3339，或相对时间 `7d`/`12h`/`30m`（表示多久以前）。
+/// Parse a point in time: a local date or date-time, RFC 3339, or an age
+/// such as `7d`, `12h` or `30m` meaning that long ago.
+pub fn parse_time(text: &str) -> Result<DateTime<Utc>> {
+    let text = text.trim();
+    let invalid = || anyhow!("Invalid time: {} (e.g. 2024-05-01, 7d, 12h)", text);
+
+    if let Some(unit) = text.chars().last().filter(|c| "mhdw".contains(*c)) {
+        if let Ok(count) = text[..text.len() - 1].parse::<i64>() {
+            let age = match unit {
+                'm' => Duration::minutes(count),
+                'h' => Duration::hours(count),
+                'd' => Duration::days(count),
+                _ => Duration::weeks(count),
+            };
+            return Ok(Utc::now() - age);
+        }
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let naive = NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M")
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S"))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default())
+        })
+        .map_err(|_| invalid())?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes() {
+        assert_eq!(parse_size("0").unwrap(), 0);
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("1B").unwrap(), 1);
+        assert_eq!(parse_size(" 10M ").unwrap(), 10 << 20);
+        assert_eq!(parse_size("1.5M").unwrap(), 3 << 19);
+        assert_eq!(parse_size("2G").unwrap(), 2 << 30);
+        assert_eq!(parse_size("1T").unwrap(), 1 << 40);
+    }
+
+    #[test]
+    fn size_suffix_case_and_spelling() {
+        for text in ["1K", "1k", "1KB", "1kb", "1KiB", "1kib"] {
+            assert_eq!(parse_size(text).unwrap(), 1024, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn invalid_sizes() {
+        for text in ["", "M", "B", "abc", "-1", "-1K", "1P", "1 M", "1MM", "1,5M"] {
+            assert!(parse_size(text).is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn relative_times() {
+        for (text, age) in [
+            ("30m", Duration::minutes(30)),
+            ("12h", Duration::hours(12)),
+            ("7d", Duration::days(7)),
+            ("2w", Duration::weeks(2)),
+        ] {
+            let expected = Utc::now() - age;
+            let parsed = parse_time(text).unwrap();
+            assert!((parsed - expected).num_seconds().abs() < 5, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn absolute_times() {
+        let local = |y, m, d, h, min| {
+            Local
+                .with_ymd_and_hms(y, m, d, h, min, 0)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        assert_eq!(parse_time("2024-05-01").unwrap(), local(2024, 5, 1, 0, 0));
+        assert_eq!(
+            parse_time("2024-05-01 12:30").unwrap(),
+            local(2024, 5, 1, 12, 30)
+        );
+        assert_eq!(
+            parse_time("2024-05-01T12:30:00").unwrap(),
+            local(2024, 5, 1, 12, 30)
+        );
+        assert_eq!(
+            parse_time("2024-05-01T12:30:00+08:00").unwrap(),
+            Utc.with_ymd_and_hms(2024, 5, 1, 4, 30, 0).unwrap()
+        );
+        assert_eq!(
+            parse_time(" 2024-05-01T04:30:00Z ").unwrap(),
+            Utc.with_ymd_and_hms(2024, 5, 1, 4, 30, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_times() {
+        for text in [
+            "",
+            "d",
+            "7x",
+            "7D",
+            "yesterday",
+            "2024-13-01",
+            "2024-02-30",
+            "2024/05/01",
+            "12:30",
+        ] {
+            assert!(parse_time(text).is_err(), "{:?}", text);
+        }
+    }
+}
@@ -1,48 +1,197 @@
-use crate::remote::ls::find_file_id_by_name;
 use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
 use futures::stream::{self, StreamExt};
 use indicatif::{ProgressBar, ProgressStyle};
 use reqwest::{header, Client};
 use serde_json::json;
+use sha1::{Digest, Sha1};
 use std::fs::OpenOptions;
-use std::io::{Seek, SeekFrom, Write};
-use std::path::Path;
+use std::io::{stdin, stdout, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{oneshot, Mutex as AsyncMutex};
+use tokio::task::JoinHandle;
 use tokio::time::{sleep, Duration};
 
-const CHUNK_SIZE: u64 = 8 * 1024 * 1024; // 每块8MB
-const MAX_CONCURRENCY: usize = 3; // 普通应用限制
+use super::bandwidth::Throttle;
+use super::concurrency::{Connections, Limiter};
+use super::ls::FileItem;
+use super::resume::{part_path, sha1_file, sha1_hex, state_path, verify_part, ResumeState};
+use super::walk::Listings;
+use crate::pager;
 
-/// 从阿里云盘下载文件（带进度条、分段下载、断点续传、403重试）
-pub async fn get_file(
+/// 默认分片大小 8MB。
+/// Chunk size used when neither a flag nor the config says otherwise.
+pub const DEFAULT_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+/// 分片大小上限 1GB。
+/// Largest accepted `--chunk-size`.
+pub const MAX_CHUNK_SIZE: u64 = 1024 * 1024 * 1024;
+const VERIFY_RETRIES: usize = 2; // 校验失败后重新下载的次数
+const HASH_CONCURRENCY: usize = 4; // --skip-if-same 同时计算哈希的文件数
+const SAVE_INTERVAL: Duration = Duration::from_secs(1); // 续传状态的保存间隔
+const URL_REFRESH_MARGIN: i64 = 60; // 链接过期前提前刷新的秒数
+const URL_REFRESH_RETRIES: usize = 3; // 单个分片因链接过期而重新获取链接的次数
+
+/// 一个待下载的远程文件。
+/// A remote file queued for download.
+#[derive(Clone, Debug)]
+pub struct DownloadJob {
+    pub file_id: String,
+    /// Remote path, used in status messages.
+    pub remote_path: String,
+    pub size: u64,
+    pub content_hash: Option<String>,
+    pub local_path: PathBuf,
+}
+
+impl DownloadJob {
+    pub fn new(item: &FileItem, remote_path: impl Into<String>, local_path: PathBuf) -> Self {
+        Self {
+            file_id: item.file_id.clone(),
+            remote_path: remote_path.into(),
+            size: item.size.unwrap_or(0),
+            content_hash: item.content_hash.clone(),
+            local_path,
+        }
+    }
+}
+
+/// 本地已存在同名文件（或未完成的 `.part`）时的处理方式。
+/// What to do when the local target, or a partial download of it, exists.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExistingPolicy {
+    /// Continue a matching `.part` download; leave finished files alone.
+    Resume,
+    /// Start over, replacing the file and any partial download.
+    Force,
+    /// Leave existing files alone.
+    SkipExisting,
+    /// Skip when size and SHA1 match the remote file, replace otherwise.
+    SkipIfSame,
+    /// Download to a free name such as `file (1).txt`.
+    Rename,
+}
+
+/// 下载选项。
+/// Options for a download batch.
+#[derive(Clone, Debug)]
+pub struct DownloadOptions {
+    /// Check size and SHA1 against the remote file once all chunks are in.
+    pub verify: bool,
+    /// How to treat existing targets. Without one, partial downloads are
+    /// resumed and existing files are asked about, or skipped when the
+    /// session is not interactive.
+    pub policy: Option<ExistingPolicy>,
+    /// Connections shared by every chunk of the batch.
+    pub connections: Connections,
+    /// Size of new chunks; a resumed download keeps the size it started with.
+    pub chunk_size: u64,
+    /// Cap on the batch's combined rate in bytes per second (`--limit-rate`).
+    pub limit_rate: Option<u64>,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            verify: true,
+            policy: None,
+            connections: Connections::default(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            limit_rate: None,
+        }
+    }
+}
+
+/// Outcome counts of a download batch.
+#[derive(Debug, Default)]
+pub struct DownloadSummary {
+    pub transferred: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+/// State shared by every file of one batch: the HTTP client, the global
+/// connection budget and the aggregate progress bar.
+struct Session<'a> {
+    client: Client,
+    token: &'a str,
+    drive_id: &'a str,
+    connections: Arc<Limiter>,
+    progress: ProgressBar,
+    verify: bool,
+    chunk_size: u64,
+    throttle: Arc<Throttle>,
+}
+
+/// 带过期时间的下载链接。
+/// A signed download URL and when it stops working.
+#[derive(Clone, Debug)]
+pub struct SignedUrl {
+    pub url: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// 单个文件的下载链接，由所有分片共享；临近过期或签名失效时重新获取。
+/// The download URL of one file, shared by its chunks. It is fetched again
+/// shortly before `expiration`, or when a chunk is refused because the
+/// signature expired, without disturbing chunks already written.
+struct DownloadUrl {
+    client: Client,
+    token: String,
+    drive_id: String,
+    file_id: String,
+    current: AsyncMutex<SignedUrl>,
+}
+
+impl DownloadUrl {
+    async fn new(session: &Session<'_>, file_id: &str) -> Result<Self> {
+        let current =
+            request_download_url(&session.client, session.token, session.drive_id, file_id).await?;
+        Ok(Self {
+            client: session.client.clone(),
+            token: session.token.to_string(),
+            drive_id: session.drive_id.to_string(),
+            file_id: file_id.to_string(),
+            current: AsyncMutex::new(current),
+        })
+    }
+
+    /// The URL to use now, refreshed first if it is about to expire.
+    async fn get(&self) -> Result<String> {
+        let mut current = self.current.lock().await;
+        let margin = chrono::Duration::seconds(URL_REFRESH_MARGIN);
+        if current
+            .expires_at
+            .is_some_and(|expires_at| expires_at - margin <= Utc::now())
+        {
+            *current = self.request().await?;
+        }
+        Ok(current.url.clone())
+    }
+
+    /// Replace `stale` after the server rejected it. Chunks that hit the
+    /// same expired URL together trigger a single request.
+    async fn refresh(&self, stale: &str) -> Result<String> {
+        let mut current = self.current.lock().await;
+        if current.url == stale {
+            *current = self.request().await?;
+        }
+        Ok(current.url.clone())
+    }
+
+    async fn request(&self) -> Result<SignedUrl> {
+        request_download_url(&self.client, &self.token, &self.drive_id, &self.file_id).await
+    }
+}
+
+/// 调用 getDownloadUrl 获取下载链接及其过期时间。
+/// Ask `getDownloadUrl` for a signed URL and its `expiration`.
+pub async fn request_download_url(
+    client: &Client,
     token: &str,
     drive_id: &str,
-    parent_file_id: &str,
-    filename: &str,
-    local_path: &Path,
-) -> Result<()> {
-    let client = Client::new();
-
-    // 1️⃣ 根据文件名查找 file_id
-    let file_id = find_file_id_by_name(token, drive_id, parent_file_id, filename).await?;
-
-    // 2️⃣ 获取文件详情（确保知道文件大小）
-    let detail_url = "https://openapi.alipan.com/adrive/v1.0/openFile/get";
-    let detail_body = json!({
-        "drive_id": drive_id,
-        "file_id": file_id
-    });
-    let detail_res = client
-        .post(detail_url)
-        .bearer_auth(token)
-        .json(&detail_body)
-        .send()
-        .await?;
-    let detail_json: serde_json::Value = detail_res.json().await?;
-    let total_size = detail_json["size"]
-        .as_u64()
-        .ok_or_else(|| anyhow!("Can not get file size"))?;
-
-    // 3️⃣ 获取下载链接
+    file_id: &str,
+) -> Result<SignedUrl> {
     let url = "https://openapi.alipan.com/adrive/v1.0/openFile/getDownloadUrl";
     let body = json!({ "drive_id": drive_id, "file_id": file_id });
     let res = client
@@ -52,73 +201,543 @@ pub async fn get_file(
         .send()
         .await?;
     let v: serde_json::Value = res.json().await?;
-    let dl_url = v["url"]
+    let url = v["url"]
         .as_str()
         .ok_or_else(|| anyhow!("No URL in response"))?
         .to_string();
+    let expires_at = v["expiration"]
+        .as_str()
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.with_timezone(&Utc));
+    Ok(SignedUrl { url, expires_at })
+}
+
+/// Whether a 403 body says the URL's signature has expired, as opposed to
+/// the account's concurrency limit.
+fn is_expired_signature(body: &str) -> bool {
+    let body = body.to_ascii_lowercase();
+    body.contains("expired") || body.contains("signature")
+}
+
+/// 将文件夹遍历结果展开为本地目录列表与下载任务（保留空文件夹）。
+/// Turn a walk of a remote folder into the local directories to create,
+/// empty ones included, and the files to download.
+pub fn plan_folder(
+    listings: &Listings,
+    folder_id: &str,
+    remote_dir: &str,
+    local_dir: &Path,
+    dirs: &mut Vec<PathBuf>,
+    jobs: &mut Vec<DownloadJob>,
+) {
+    dirs.push(local_dir.to_path_buf());
+    for item in listings.get(folder_id).into_iter().flatten() {
+        let remote_path = format!("{}/{}", remote_dir.trim_end_matches('/'), item.name);
+        let local_path = local_dir.join(&item.name);
+        if item.kind == "folder" {
+            plan_folder(
+                listings,
+                &item.file_id,
+                &remote_path,
+                &local_path,
+                dirs,
+                jobs,
+            );
+        } else {
+            jobs.push(DownloadJob::new(item, remote_path, local_path));
+        }
+    }
+}
 
-    // 4️⃣ 进度条
-    let pb = ProgressBar::new(total_size);
+/// 批量下载：多个文件并行，分片共享全局并发额度，使用一个总进度条。
+/// Download a batch of files in parallel. Chunks of every file share one
+/// connection budget and advance one aggregate progress bar. Existing
+/// targets are handled by `options.policy` before anything starts.
+pub async fn download(
+    token: &str,
+    drive_id: &str,
+    jobs: Vec<DownloadJob>,
+    options: &DownloadOptions,
+) -> DownloadSummary {
+    let mut summary = DownloadSummary::default();
+    let pending = apply_policy(jobs, options.policy, &mut summary).await;
+
+    let total_bytes = pending.iter().map(|job| job.size).sum();
+    let file_count = pending.len();
+    let pb = ProgressBar::new(total_bytes);
     pb.set_style(
         ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")
+            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} {msg} ({eta})")
             .unwrap()
             .progress_chars("=>-"),
     );
+    let session = Session {
+        client: Client::new(),
+        token,
+        drive_id,
+        connections: Limiter::new(options.connections),
+        progress: pb,
+        verify: options.verify,
+        chunk_size: options.chunk_size,
+        throttle: Throttle::new(options.limit_rate),
+    };
+    let tuner = session.connections.spawn_tuner(session.progress.clone());
+
+    let session_ref = &session;
+    let mut results = stream::iter(pending)
+        .map(|job| async move {
+            let result = download_one(session_ref, &job).await;
+            (job, result)
+        })
+        .buffer_unordered(session.connections.max());
+
+    while let Some((job, result)) = results.next().await {
+        match result {
+            Ok(verified) => {
+                summary.transferred += 1;
+                let note = if verified { " (SHA1 verified)" } else { "" };
+                session.progress.println(format!(
+                    "✅ File Saved to: {}{}",
+                    job.local_path.display(),
+                    note
+                ));
+            }
+            Err(e) => {
+                summary.failed += 1;
+                session
+                    .progress
+                    .println(format!("❌ {}: {}", job.remote_path, e));
+            }
+        }
+        if file_count > 1 {
+            let done = summary.transferred + summary.failed;
+            session
+                .progress
+                .set_message(format!("{}/{} files", done, file_count));
+        }
+    }
+
+    if let Some(tuner) = tuner {
+        tuner.abort();
+    }
+    session.progress.finish_and_clear();
+    summary
+}
+
+/// 按策略处理本地已存在的目标，返回需要下载的任务。
+/// Decide what to do with each job whose target exists, asking when no
+/// policy was given and the session is interactive. A capital letter at the
+/// prompt applies the answer to every remaining file. Files to compare are
+/// hashed in parallel once every answer is in.
+async fn apply_policy(
+    jobs: Vec<DownloadJob>,
+    policy: Option<ExistingPolicy>,
+    summary: &mut DownloadSummary,
+) -> Vec<DownloadJob> {
+    let interactive = pager::interactive();
+    let mut for_all = policy;
+    let mut pending = Vec::new();
+    let mut compare = Vec::new();
+    for mut job in jobs {
+        if for_all == Some(ExistingPolicy::Force) {
+            ResumeState::discard(&job.local_path);
+        }
+        if !job.local_path.exists() {
+            pending.push(job);
+            continue;
+        }
+        let choice = match for_all {
+            Some(choice) => choice,
+            None if interactive => {
+                let (choice, all) = ask_existing(&job.local_path);
+                if all {
+                    for_all = Some(choice);
+                }
+                choice
+            }
+            None => {
+                println!(
+                    "⏭️  Skipped (exists, use --force, --skip-if-same or --rename): {}",
+                    job.local_path.display()
+                );
+                summary.skipped += 1;
+                continue;
+            }
+        };
+        match choice {
+            ExistingPolicy::Resume | ExistingPolicy::SkipExisting => {
+                println!("⏭️  Skipped (exists): {}", job.local_path.display());
+                summary.skipped += 1;
+            }
+            ExistingPolicy::SkipIfSame => compare.push(job),
+            ExistingPolicy::Force => {
+                ResumeState::discard(&job.local_path);
+                pending.push(job);
+            }
+            ExistingPolicy::Rename => {
+                job.local_path = free_name(&job);
+                println!("📝 Saving as {}", job.local_path.display());
+                pending.push(job);
+            }
+        }
+    }
+
+    if !compare.is_empty() {
+        println!("🔍 Comparing {} existing files...", compare.len());
+    }
+    let mut compared = stream::iter(compare)
+        .map(|job| async move {
+            let same = same_as_remote(&job).await;
+            (job, same)
+        })
+        .buffer_unordered(HASH_CONCURRENCY);
+    while let Some((job, same)) = compared.next().await {
+        if same {
+            println!("⏭️  Skipped (identical): {}", job.local_path.display());
+            summary.skipped += 1;
+        } else {
+            ResumeState::discard(&job.local_path);
+            pending.push(job);
+        }
+    }
+    pending
+}
+
+/// Prompt for what to do with an existing file. Returns the choice and
+/// whether it applies to all remaining files; end of input means skip.
+fn ask_existing(path: &Path) -> (ExistingPolicy, bool) {
+    loop {
+        print!(
+            "⚠️  {} already exists. [o]verwrite, [s]kip, [c]ompare (skip if same), [r]ename (capital = apply to all): ",
+            path.display()
+        );
+        let _ = stdout().flush();
+        let mut answer = String::new();
+        if stdin().read_line(&mut answer).unwrap_or(0) == 0 {
+            return (ExistingPolicy::SkipExisting, false);
+        }
+        let Some(key) = answer.trim().chars().next() else {
+            continue;
+        };
+        let choice = match key.to_ascii_lowercase() {
+            'o' => ExistingPolicy::Force,
+            's' => ExistingPolicy::SkipExisting,
+            'c' => ExistingPolicy::SkipIfSame,
+            'r' => ExistingPolicy::Rename,
+            _ => continue,
+        };
+        return (choice, key.is_ascii_uppercase());
+    }
+}
 
-    // 5️⃣ 打开/创建目标文件（断点续传）
-    let mut file = OpenOptions::new()
-        .create(true)
-        .write(true)
-        .open(local_path)?;
-    let downloaded = file.metadata()?.len();
-    if downloaded > 0 && downloaded < total_size {
-        file.seek(SeekFrom::Start(downloaded))?;
-        pb.set_position(downloaded);
+/// Whether the local file has the remote size and SHA1. The size is checked
+/// first so differing files are never hashed; without a remote hash the
+/// size alone decides. Hashing runs on the blocking pool.
+async fn same_as_remote(job: &DownloadJob) -> bool {
+    let Ok(metadata) = std::fs::metadata(&job.local_path) else {
+        return false;
+    };
+    if metadata.len() != job.size {
+        return false;
     }
+    let Some(hash) = job.content_hash.clone() else {
+        return true;
+    };
+    let path = job.local_path.clone();
+    tokio::task::spawn_blocking(move || {
+        sha1_file(&path).is_ok_and(|local| local.eq_ignore_ascii_case(&hash))
+    })
+    .await
+    .unwrap_or(false)
+}
 
-    // 6️⃣ 构建下载分片
-    let chunks: Vec<(u64, u64)> = (downloaded..total_size)
-        .step_by(CHUNK_SIZE as usize)
-        .map(|start| {
-            let end = std::cmp::min(start + CHUNK_SIZE - 1, total_size - 1);
-            (start, end)
+/// First `name (n).ext` next to the target that is free, or whose partial
+/// download belongs to this same remote file.
+fn free_name(job: &DownloadJob) -> PathBuf {
+    let path = &job.local_path;
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| path.with_file_name(format!("{} ({}){}", stem, n, ext)))
+        .find(|candidate| {
+            !candidate.exists()
+                && (!ResumeState::exists(candidate)
+                    || ResumeState::load_matching(
+                        candidate,
+                        &job.file_id,
+                        job.size,
+                        job.content_hash.as_deref(),
+                    )
+                    .is_some())
         })
-        .collect();
+        .unwrap_or_else(|| path.clone())
+}
+
+/// 下载单个文件（分段下载、断点续传、403重试、SHA1 校验）。数据先写入 `.part`
+/// 文件，每完成一个分片就更新状态文件，校验通过后才重命名为目标文件。
+/// Download one file. Chunks go to `<file>.part` and are recorded in a
+/// sidecar state as they complete, so an interrupted download resumes
+/// exactly the missing ranges. Unless verification is off, the result is
+/// checked against the remote size and SHA1 and bad chunks are fetched again
+/// before the file is renamed into place. Returns whether the SHA1 matched.
+async fn download_one(session: &Session<'_>, job: &DownloadJob) -> Result<bool> {
+    let local_path = &job.local_path;
+    let part = part_path(local_path);
+
+    // 1️⃣ 获取下载链接
+    let dl_url = Arc::new(DownloadUrl::new(session, &job.file_id).await?);
+
+    // 2️⃣ 读取续传状态；远程文件已变化时丢弃旧的 .part
+    let content_hash = job.content_hash.as_deref();
+    let state = match ResumeState::load_matching(local_path, &job.file_id, job.size, content_hash) {
+        Some(state) => {
+            session.progress.println(format!(
+                "⏯️  Resuming {} ({}/{} chunks done)",
+                local_path.display(),
+                state.chunk_count() - state.missing().len(),
+                state.chunk_count()
+            ));
+            state
+        }
+        None => {
+            if ResumeState::exists(local_path) {
+                session.progress.println(format!(
+                    "♻️  Discarding stale partial download: {}",
+                    part.display()
+                ));
+                ResumeState::discard(local_path);
+            }
+            let file = OpenOptions::new()
+                .create(true)
+                .truncate(true)
+                .write(true)
+                .open(&part)?;
+            file.set_len(job.size)?;
+            let state = ResumeState::new(&job.file_id, job.size, content_hash, session.chunk_size);
+            state.save(local_path)?;
+            state
+        }
+    };
+    session.progress.inc(state.completed_bytes());
+    let state = Arc::new(Mutex::new(state));
+
+    let mut retries = 0;
+    let verified = loop {
+        // 3️⃣ 只下载尚未完成的分片
+        let (stop, stopped) = oneshot::channel();
+        let saver = spawn_saver(Arc::clone(&state), local_path.clone(), stopped);
+        let failures = fetch_chunks(session, &dl_url, local_path, &state).await;
+        let _ = stop.send(());
+        saver.await??;
+        if !failures.is_empty() {
+            return Err(incomplete_error(failures, &part));
+        }
+        let missing = state.lock().unwrap().missing().len();
+        if missing > 0 {
+            return Err(anyhow!(
+                "{} chunks incomplete; run get again to resume",
+                missing
+            ));
+        }
+        if !session.verify {
+            break false;
+        }
 
-    // 7️⃣ 并发分段下载
-    let client_ref = &client;
-    let pb_ref = &pb;
-    let path_ref = local_path.to_path_buf();
+        // 4️⃣ 校验大小与 SHA1，不符的分片重新下载
+        let snapshot = state.lock().unwrap().clone();
+        let path = local_path.clone();
+        let hash = job.content_hash.clone();
+        let bad =
+            tokio::task::spawn_blocking(move || verify_part(&path, &snapshot, hash.as_deref()))
+                .await??;
+        if bad.is_empty() {
+            break content_hash.is_some();
+        }
+        let (ranges, refetch) = {
+            let state = state.lock().unwrap();
+            let ranges: Vec<(u64, u64)> =
+                bad.iter().map(|&index| state.chunk_range(index)).collect();
+            let refetch: u64 = ranges.iter().map(|(start, end)| end - start + 1).sum();
+            let ranges = bad
+                .iter()
+                .zip(&ranges)
+                .map(|(index, (start, end))| format!("#{} ({}-{})", index, start, end))
+                .collect::<Vec<_>>()
+                .join(", ");
+            (ranges, refetch)
+        };
+        if retries == VERIFY_RETRIES {
+            return Err(anyhow!("SHA1 mismatch in chunks {}", ranges));
+        }
+        retries += 1;
+        session.progress.println(format!(
+            "⚠️  {}: SHA1 mismatch, refetching chunks {}",
+            job.remote_path, ranges
+        ));
+        {
+            let mut state = state.lock().unwrap();
+            for &index in &bad {
+                state.unmark(index);
+            }
+        }
+        save_if_dirty(&state, local_path).await?;
+        let position = session.progress.position();
+        session
+            .progress
+            .set_position(position.saturating_sub(refetch));
+    };
+
+    // 5️⃣ 完成后重命名并删除状态文件
+    std::fs::rename(&part, local_path)?;
+    let _ = std::fs::remove_file(state_path(local_path));
+    Ok(verified)
+}
+
+/// 后台保存续传状态：最多每秒写一次，在锁外序列化并于阻塞线程池写文件；收到停止信号时做最后一次保存。
+/// Save the resume state in the background, at most once per
+/// `SAVE_INTERVAL`. A snapshot is taken under the lock and written on the
+/// blocking pool. When `stop` fires, or its sender is dropped, pending
+/// changes are saved one last time.
+fn spawn_saver(
+    state: Arc<Mutex<ResumeState>>,
+    local_path: PathBuf,
+    mut stop: oneshot::Receiver<()>,
+) -> JoinHandle<Result<()>> {
+    tokio::spawn(async move {
+        loop {
+            let stopping = tokio::select! {
+                _ = sleep(SAVE_INTERVAL) => false,
+                _ = &mut stop => true,
+            };
+            save_if_dirty(&state, &local_path).await?;
+            if stopping {
+                return Ok(());
+            }
+        }
+    })
+}
+
+/// Write the state if it changed, without holding its lock during I/O.
+async fn save_if_dirty(state: &Mutex<ResumeState>, local_path: &Path) -> Result<()> {
+    let Some(snapshot) = state.lock().unwrap().take_dirty() else {
+        return Ok(());
+    };
+    let local_path = local_path.to_path_buf();
+    tokio::task::spawn_blocking(move || snapshot.save(&local_path)).await?
+}
+
+/// 并发下载状态中尚未完成的分片（占用全局并发额度），每完成一个就写入 `.part` 并在状态中标记。
+/// Fetch the chunks still missing from `state`, each under a permit of the
+/// global connection budget, writing them into the `.part` file and marking
+/// them done as each one lands; [`spawn_saver`] persists the marks. Returns the chunks that failed, as
+/// inclusive byte ranges with their errors.
+async fn fetch_chunks(
+    session: &Session<'_>,
+    dl_url: &Arc<DownloadUrl>,
+    local_path: &Path,
+    state: &Arc<Mutex<ResumeState>>,
+) -> Vec<ChunkFailure> {
+    let chunks: Vec<(usize, u64, u64)> = {
+        let state = state.lock().unwrap();
+        state
+            .missing()
+            .into_iter()
+            .map(|index| {
+                let (start, end) = state.chunk_range(index);
+                (index, start, end)
+            })
+            .collect()
+    };
 
     stream::iter(chunks)
-        .map(|(start, end)| {
-            let client = client_ref.clone();
-            let dl_url = dl_url.clone();
-            let path = path_ref.clone();
-            let pb = pb_ref.clone();
+        .map(|(index, start, end)| {
+            let client = session.client.clone();
+            let dl_url = Arc::clone(dl_url);
+            let part = part_path(local_path);
+            let pb = session.progress.clone();
+            let connections = Arc::clone(&session.connections);
+            let state = Arc::clone(state);
+            let throttle = Arc::clone(&session.throttle);
 
-            tokio::spawn(async move {
+            let task = tokio::spawn(async move {
+                let _permit = connections.acquire().await?;
                 let range_header = format!("bytes={}-{}", start, end);
                 let mut retry_count = 0;
+                let mut refresh_count = 0;
 
                 loop {
+                    let url = dl_url.get().await?;
                     let resp = client
-                        .get(&dl_url)
+                        .get(&url)
                         .header(header::RANGE, &range_header)
                         .send()
                         .await;
 
                     match resp {
                         Ok(r) if r.status().is_success() || r.status() == 206 => {
-                            let bytes = r.bytes().await?;
-                            let mut f = OpenOptions::new().write(true).open(&path)?;
+                            // 边接收边写入 .part 并增量计算 SHA1，不在内存中缓存整个分片
+                            let expected = end - start + 1;
+                            let mut f = OpenOptions::new().write(true).open(&part)?;
                             f.seek(SeekFrom::Start(start))?;
-                            f.write_all(&bytes)?;
-                            pb.inc(bytes.len() as u64);
+                            let mut hasher = Sha1::new();
+                            let mut received = 0;
+                            let mut body = r.bytes_stream();
+                            let mut failure = None;
+                            while let Some(piece) = body.next().await {
+                                let piece = match piece {
+                                    Ok(piece) => piece,
+                                    Err(e) => {
+                                        failure = Some(anyhow!("Download failed: {}", e));
+                                        break;
+                                    }
+                                };
+                                let take = piece.len().min((expected - received) as usize);
+                                throttle.consume(take as u64).await;
+                                f.write_all(&piece[..take])?;
+                                hasher.update(&piece[..take]);
+                                received += take as u64;
+                                pb.inc(take as u64);
+                                if received == expected {
+                                    break;
+                                }
+                            }
+                            if received != expected {
+                                pb.set_position(pb.position().saturating_sub(received));
+                                let error = failure.unwrap_or_else(|| {
+                                    anyhow!(
+                                        "Short read for bytes {}-{}: got {} bytes",
+                                        start,
+                                        end,
+                                        received
+                                    )
+                                });
+                                if retry_count < 3 {
+                                    retry_count += 1;
+                                    continue;
+                                }
+                                return Err(error);
+                            }
+                            state.lock().unwrap().mark_done(index, sha1_hex(hasher));
                             break;
                         }
-                        Ok(r) if r.status().as_u16() == 403 && retry_count < 3 => {
+                        Ok(r) if r.status().as_u16() == 403 => {
+                            let body = r.text().await.unwrap_or_default();
+                            if is_expired_signature(&body) && refresh_count < URL_REFRESH_RETRIES {
+                                pb.println("🔄 Download URL expired, requesting a new one...");
+                                refresh_count += 1;
+                                dl_url.refresh(&url).await?;
+                                continue;
+                            }
+                            if retry_count == 3 {
+                                return Err(anyhow!("Download Error: 403 Forbidden"));
+                            }
+                            connections.back_off();
                             eprintln!("403 Concurrent limit, retry after 3 seconds...");
                             retry_count += 1;
                             sleep(Duration::from_secs(3)).await;
@@ -135,13 +754,43 @@ pub async fn get_file(
                     }
                 }
                 Ok::<(), anyhow::Error>(())
-            })
+            });
+            async move {
+                let error = match task.await {
+                    Ok(Ok(())) => return None,
+                    Ok(Err(e)) => e,
+                    Err(e) => anyhow!("Chunk task failed: {}", e),
+                };
+                Some(ChunkFailure { start, end, error })
+            }
         })
-        .buffer_unordered(MAX_CONCURRENCY)
-        .collect::<Vec<_>>()
-        .await;
+        .buffer_unordered(session.connections.max())
+        .filter_map(|failure| async move { failure })
+        .collect()
+        .await
+}
 
-    pb.finish_with_message("✅ Download Complete");
-    println!("✅ File Saved to: {}", local_path.display());
-    Ok(())
+/// 下载失败的分片。
+/// A chunk that could not be fetched.
+struct ChunkFailure {
+    start: u64,
+    end: u64,
+    error: anyhow::Error,
+}
+
+/// Error for a download that stopped with chunks missing, listing the failed
+/// byte ranges. The `.part` file and its state are kept for resuming.
+fn incomplete_error(mut failures: Vec<ChunkFailure>, part: &Path) -> anyhow::Error {
+    failures.sort_by_key(|f| f.start);
+    let ranges = failures
+        .iter()
+        .map(|f| format!("bytes {}-{} ({})", f.start, f.end, f.error))
+        .collect::<Vec<_>>()
+        .join("; ");
+    anyhow!(
+        "{} chunks failed: {}. Partial download kept in {}; run get again to resume",
+        failures.len(),
+        ranges,
+        part.display()
+    )
 }
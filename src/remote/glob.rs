@@ -0,0 +1,555 @@
+use anyhow::Result;
+use futures::future::BoxFuture;
+
+use super::cache::ListingCache;
+
+/// One word of a command line. `text` is the unquoted value; `pattern` is set
+/// only when the word contains unquoted glob characters, with any quoted or
+/// backslash-escaped metacharacters escaped so they match literally.
+#[derive(Clone, Debug)]
+pub struct Word {
+    pub text: String,
+    pub pattern: Option<String>,
+}
+
+fn is_magic(c: char) -> bool {
+    matches!(c, '*' | '?' | '[' | '{')
+}
+
+fn is_meta(c: char) -> bool {
+    matches!(c, '*' | '?' | '[' | ']' | '{' | '}' | ',' | '\\')
+}
+
+/// Push a quoted or escaped character: literal in `text`, escaped in `pattern`.
+fn literal(c: char, text: &mut String, pattern: &mut String) {
+    text.push(c);
+    if is_meta(c) {
+        pattern.push('\\');
+    }
+    pattern.push(c);
+}
+
+/// Split a command line the same way `shlex::Shlex` does, additionally
+/// remembering which glob characters were quoted.
+/// 按 Shlex 规则拆分命令行，同时记录哪些通配符处于引号中（引号可关闭通配）。
+pub fn split_words(line: &str) -> Result<Vec<Word>> {
+    Ok(tokenize(line, false)?
+        .0
+        .into_iter()
+        .map(|(_, word)| word)
+        .collect())
+}
+
+/// Split a partially typed line for completion: unterminated quotes are
+/// tolerated, and a trailing separator yields an empty final word. Each word
+/// comes with the byte offset where it starts.
+/// 为补全拆分尚未输入完整的命令行：容忍未闭合的引号，末尾为空白时追加空词。
+pub fn split_partial(line: &str) -> Vec<(usize, String)> {
+    let (words, open) = tokenize(line, true).unwrap_or_default();
+    let mut words: Vec<(usize, String)> = words
+        .into_iter()
+        .map(|(start, word)| (start, word.text))
+        .collect();
+    if !open {
+        words.push((line.len(), String::new()));
+    }
+    words
+}
+
+/// Core tokenizer. The returned flag tells whether the input ended inside a
+/// word (as opposed to after a separator).
+fn tokenize(line: &str, lenient: bool) -> Result<(Vec<(usize, Word)>, bool)> {
+    let mut words = Vec::new();
+    let mut open = false;
+    let mut chars = line.char_indices().peekable();
+
+    loop {
+        while let Some(&(_, c)) = chars.peek() {
+            match c {
+                ' ' | '\t' | '\n' => {
+                    chars.next();
+                }
+                '#' => {
+                    for (_, c) in chars.by_ref() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                _ => break,
+            }
+        }
+        let Some(&(start, _)) = chars.peek() else {
+            break;
+        };
+        let unterminated = |what: &str| -> Result<()> {
+            if lenient {
+                Ok(())
+            } else {
+                anyhow::bail!("{}", what)
+            }
+        };
+
+        let mut text = String::new();
+        let mut pattern = String::new();
+        let mut magic = false;
+        open = true;
+
+        while let Some((_, c)) = chars.next() {
+            match c {
+                ' ' | '\t' | '\n' => {
+                    open = false;
+                    break;
+                }
+                '\'' => loop {
+                    match chars.next().map(|(_, c)| c) {
+                        Some('\'') => break,
+                        Some(ch) => literal(ch, &mut text, &mut pattern),
+                        None => {
+                            unterminated("Unterminated single quote")?;
+                            break;
+                        }
+                    }
+                },
+                '"' => loop {
+                    match chars.next().map(|(_, c)| c) {
+                        Some('"') => break,
+                        Some('\\') => match chars.next().map(|(_, c)| c) {
+                            Some(ch @ ('$' | '`' | '"' | '\\')) => {
+                                literal(ch, &mut text, &mut pattern)
+                            }
+                            Some('\n') => {}
+                            Some(ch) => {
+                                literal('\\', &mut text, &mut pattern);
+                                literal(ch, &mut text, &mut pattern);
+                            }
+                            None => {
+                                unterminated("Unterminated double quote")?;
+                                break;
+                            }
+                        },
+                        Some(ch) => literal(ch, &mut text, &mut pattern),
+                        None => {
+                            unterminated("Unterminated double quote")?;
+                            break;
+                        }
+                    }
+                },
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('\n') => {}
+                    Some(ch) => literal(ch, &mut text, &mut pattern),
+                    None => unterminated("Trailing backslash")?,
+                },
+                c => {
+                    text.push(c);
+                    pattern.push(c);
+                    magic |= is_magic(c);
+                }
+            }
+        }
+
+        words.push((
+            start,
+            Word {
+                text,
+                pattern: magic.then_some(pattern),
+            },
+        ));
+    }
+
+    Ok((words, open))
+}
+
+/// Quote a word so that it round-trips through `split_words` (and `Shlex`)
+/// unchanged and is never glob-expanded. Plain words are returned as-is.
+/// 为补全结果加引号：保证经 Shlex 解析后原样还原，且不会被通配展开。
+pub fn quote_word(word: &str) -> String {
+    let needs_quotes = word.is_empty()
+        || word.starts_with('#')
+        || word
+            .chars()
+            .any(|c| c.is_ascii_whitespace() || matches!(c, '\'' | '"' | '\\') || is_meta(c));
+    if !needs_quotes {
+        return word.to_string();
+    }
+    shlex::try_quote(word)
+        .map(|q| q.into_owned())
+        .unwrap_or_else(|_| word.to_string())
+}
+
+/// Expand `{a,b}` alternatives textually (nested braces supported).
+/// 展开 `{a,b}` 形式的花括号（支持嵌套）。
+pub fn expand_braces(pattern: &str) -> Vec<String> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '{' => {
+                let mut depth = 0;
+                let mut commas = Vec::new();
+                let mut j = i;
+                while j < chars.len() {
+                    match chars[j] {
+                        '\\' => j += 1,
+                        '{' => depth += 1,
+                        '}' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        ',' if depth == 1 => commas.push(j),
+                        _ => {}
+                    }
+                    j += 1;
+                }
+                if j < chars.len() && !commas.is_empty() {
+                    let prefix: String = chars[..i].iter().collect();
+                    let suffix: String = chars[j + 1..].iter().collect();
+                    let mut bounds = vec![i];
+                    bounds.extend(commas);
+                    bounds.push(j);
+                    return bounds
+                        .windows(2)
+                        .flat_map(|w| {
+                            let alt: String = chars[w[0] + 1..w[1]].iter().collect();
+                            expand_braces(&format!("{}{}{}", prefix, alt, suffix))
+                        })
+                        .collect();
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    vec![pattern.to_string()]
+}
+
+/// Whether a single path component contains unescaped `*`, `?` or `[`.
+pub fn has_magic(component: &str) -> bool {
+    let mut chars = component.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '*' | '?' | '[' => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Drop the escaping backslashes from a pattern component.
+pub fn unescape(component: &str) -> String {
+    let mut out = String::new();
+    let mut chars = component.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(next) = chars.next() {
+                out.push(next);
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Match one path component against a name. As in the shell, a leading `.`
+/// in the name must be matched explicitly.
+/// 匹配单个路径片段；与 shell 一致，以 `.` 开头的名称需显式匹配。
+pub fn match_name(pattern: &str, name: &str) -> bool {
+    if name.starts_with('.') && !pattern.starts_with('.') && !pattern.starts_with("\\.") {
+        return false;
+    }
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    match_chars(&pattern, &name)
+}
+
+fn match_chars(p: &[char], n: &[char]) -> bool {
+    match p.first() {
+        None => n.is_empty(),
+        Some('*') => {
+            let rest = &p[1..];
+            if rest.is_empty() {
+                return true;
+            }
+            (0..=n.len()).any(|k| match_chars(rest, &n[k..]))
+        }
+        Some('?') => !n.is_empty() && match_chars(&p[1..], &n[1..]),
+        Some('[') => match match_class(&p[1..], n.first().copied()) {
+            Some((consumed, matched)) => matched && match_chars(&p[1 + consumed..], &n[1..]),
+            // 没有闭合的 `]`：按字面量处理
+            None => n.first() == Some(&'[') && match_chars(&p[1..], &n[1..]),
+        },
+        Some('\\') if p.len() > 1 => n.first() == Some(&p[1]) && match_chars(&p[2..], &n[1..]),
+        Some(c) => n.first() == Some(c) && match_chars(&p[1..], &n[1..]),
+    }
+}
+
+/// Parse a `[...]` class (starting after the `[`) and test `c` against it.
+/// Returns the number of pattern chars consumed and whether it matched.
+fn match_class(p: &[char], c: Option<char>) -> Option<(usize, bool)> {
+    let mut i = 0;
+    let negate = matches!(p.first(), Some('!') | Some('^'));
+    if negate {
+        i += 1;
+    }
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        let mut lo = *p.get(i)?;
+        if lo == ']' && !first {
+            break;
+        }
+        first = false;
+        if lo == '\\' {
+            i += 1;
+            lo = *p.get(i)?;
+        }
+        let mut hi = lo;
+        if p.get(i + 1) == Some(&'-') && p.get(i + 2).is_some_and(|&h| h != ']') {
+            i += 2;
+            hi = p[i];
+            if hi == '\\' {
+                i += 1;
+                hi = *p.get(i)?;
+            }
+        }
+        if let Some(c) = c {
+            if lo <= c && c <= hi {
+                matched = true;
+            }
+        }
+        i += 1;
+    }
+    Some((i + 1, c.is_some() && matched != negate))
+}
+
+/// Match the remaining pattern components against folder listings, starting
+/// at `folder_id`, and return the matching paths relative to that folder.
+/// 从指定文件夹开始，逐级列目录匹配剩余的通配片段，返回相对路径列表。
+pub async fn expand_in(
+    cache: &ListingCache,
+    token: &str,
+    drive_id: &str,
+    folder_id: &str,
+    components: &[String],
+    dir_only: bool,
+) -> Result<Vec<String>> {
+    let walker = Walker {
+        cache,
+        token,
+        drive_id,
+        dir_only,
+    };
+    let mut out = Vec::new();
+    walker
+        .walk(folder_id.to_string(), String::new(), components, &mut out)
+        .await?;
+    let mut seen = std::collections::HashSet::new();
+    out.retain(|path| seen.insert(path.clone()));
+    Ok(out)
+}
+
+fn join(rel: &str, name: &str) -> String {
+    if rel.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", rel, name)
+    }
+}
+
+struct Walker<'a> {
+    cache: &'a ListingCache,
+    token: &'a str,
+    drive_id: &'a str,
+    dir_only: bool,
+}
+
+impl<'a> Walker<'a> {
+    fn walk(
+        &'a self,
+        folder_id: String,
+        rel: String,
+        components: &'a [String],
+        out: &'a mut Vec<String>,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let Some((first, rest)) = components.split_first() else {
+                if !rel.is_empty() {
+                    out.push(rel);
+                }
+                return Ok(());
+            };
+
+            let items = self
+                .cache
+                .items(self.token, self.drive_id, &folder_id)
+                .await?;
+
+            if first == "**" {
+                if rest.is_empty() {
+                    // 单独的 `**`：递归匹配所有条目
+                    for item in items.iter().filter(|i| !i.name.starts_with('.')) {
+                        let path = join(&rel, &item.name);
+                        let is_dir = item.kind == "folder";
+                        if is_dir || !self.dir_only {
+                            out.push(path.clone());
+                        }
+                        if is_dir {
+                            self.walk(item.file_id.clone(), path, components, out)
+                                .await?;
+                        }
+                    }
+                    return Ok(());
+                }
+                // `**` 匹配零层目录
+                self.walk(folder_id.clone(), rel.clone(), rest, out).await?;
+                // `**` 匹配一层或多层目录
+                for item in items
+                    .iter()
+                    .filter(|i| i.kind == "folder" && !i.name.starts_with('.'))
+                {
+                    self.walk(
+                        item.file_id.clone(),
+                        join(&rel, &item.name),
+                        components,
+                        out,
+                    )
+                    .await?;
+                }
+                return Ok(());
+            }
+
+            for item in items.iter().filter(|i| match_name(first, &i.name)) {
+                let path = join(&rel, &item.name);
+                if rest.is_empty() {
+                    if !self.dir_only || item.kind == "folder" {
+                        out.push(path);
+                    }
+                } else if item.kind == "folder" {
+                    self.walk(item.file_id.clone(), path, rest, out).await?;
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards_and_dotfiles() {
+        assert!(match_name("*.txt", "a.txt"));
+        assert!(!match_name("*.txt", "a.rs"));
+        assert!(match_name("?.rs", "a.rs"));
+        assert!(!match_name("?.rs", "ab.rs"));
+        assert!(!match_name("*", ".hidden"));
+        assert!(!match_name("?hidden", ".hidden"));
+        assert!(match_name(".*", ".hidden"));
+        assert!(match_name("\\.h*", ".hidden"));
+    }
+
+    #[test]
+    fn classes() {
+        assert!(match_name("[abc].rs", "b.rs"));
+        assert!(!match_name("[abc].rs", "d.rs"));
+        assert!(match_name("[a-c]x", "cx"));
+        assert!(!match_name("[a-c]x", "dx"));
+        assert!(match_name("[]]", "]"));
+        assert!(match_name("[a-]", "-"));
+        assert!(match_name("[a\\-z]", "-"));
+        assert!(!match_name("[a\\-z]", "b"));
+        // An unclosed class is matched literally.
+        assert!(match_name("[ab", "[ab"));
+        assert!(!match_name("[ab", "a"));
+    }
+
+    #[test]
+    fn negated_classes() {
+        assert!(match_name("[!a-c]x", "dx"));
+        assert!(!match_name("[!a-c]x", "bx"));
+        assert!(match_name("[^a]", "b"));
+        assert!(!match_name("[^a]", "a"));
+        assert!(!match_name("[!a]", ""));
+    }
+
+    #[test]
+    fn braces() {
+        assert_eq!(expand_braces("a{b,c}d"), ["abd", "acd"]);
+        assert_eq!(expand_braces("x{a,b{1,2}}"), ["xa", "xb1", "xb2"]);
+        assert_eq!(expand_braces("{a,b}{1,2}"), ["a1", "a2", "b1", "b2"]);
+        assert_eq!(expand_braces("{a}"), ["{a}"]);
+        assert_eq!(expand_braces("{a,b"), ["{a,b"]);
+        assert_eq!(expand_braces("\\{a,b}"), ["\\{a,b}"]);
+        assert_eq!(expand_braces("{a\\,b,c}"), ["a\\,b", "c"]);
+    }
+
+    #[test]
+    fn quoted_and_escaped_metacharacters() {
+        let words = split_words("ls '*.txt' \"a b\" c\\*d *.rs '[x]'*").unwrap();
+        let texts: Vec<&str> = words.iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(texts, ["ls", "*.txt", "a b", "c*d", "*.rs", "[x]*"]);
+        assert_eq!(words[1].pattern, None);
+        assert_eq!(words[3].pattern, None);
+        assert_eq!(words[4].pattern.as_deref(), Some("*.rs"));
+
+        let pattern = words[5].pattern.as_deref().unwrap();
+        assert_eq!(pattern, "\\[x\\]*");
+        assert!(!has_magic("\\[x\\]"));
+        assert!(match_name(pattern, "[x]y"));
+        assert!(!match_name(pattern, "xy"));
+        assert_eq!(unescape("\\[x\\]"), "[x]");
+    }
+
+    #[test]
+    fn unterminated_input() {
+        assert!(split_words("cat 'a").is_err());
+        assert!(split_words("cat \"a").is_err());
+        assert!(split_words("cat a\\").is_err());
+    }
+
+    #[test]
+    fn partial_lines() {
+        assert_eq!(
+            split_partial("get \"my fi"),
+            [(0, "get".to_string()), (4, "my fi".to_string())]
+        );
+        assert_eq!(
+            split_partial("ls "),
+            [(0, "ls".to_string()), (3, String::new())]
+        );
+    }
+
+    #[test]
+    fn quote_word_round_trips() {
+        assert_eq!(quote_word("plain.txt"), "plain.txt");
+        for word in [
+            "plain.txt",
+            "",
+            "a b",
+            "tab\there",
+            "it's",
+            "say \"hi\"",
+            "back\\slash",
+            "#tag",
+            "*.txt",
+            "[x]",
+            "{a,b}",
+            "文件 名.txt",
+        ] {
+            let quoted = quote_word(word);
+            assert_eq!(shlex::split(&quoted), Some(vec![word.to_string()]));
+            let words = split_words(&quoted).unwrap();
+            assert_eq!(words.len(), 1, "{}", quoted);
+            assert_eq!(words[0].text, word);
+            assert_eq!(words[0].pattern, None);
+        }
+    }
+}
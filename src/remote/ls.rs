@@ -1,7 +1,14 @@
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use colored::Colorize;
-use reqwest::Client;
+use reqwest::{header, Client, Response, StatusCode};
 use serde::Deserialize;
+use tokio::time::{sleep, Duration};
+
+use super::cache::ListingCache;
+use super::filter::ItemFilter;
+use super::output::{print_listing, render_items, ListFormat, Listing, Record};
+use super::walk::{walk, Listings};
+use crate::pager::{self, Page};
 
 #[derive(Clone, Debug, Default)]
 pub struct ListOptions {
@@ -10,6 +17,41 @@ pub struct ListOptions {
     pub order_by: Option<String>,
     pub order_direction: Option<String>,
     pub fetch_all: bool,
+    /// Descend into subfolders (`ls -R`).
+    pub recursive: bool,
+    /// Server-side `type` filter: `file` or `folder`.
+    pub kind: Option<String>,
+    /// Server-side `category` filter, comma-separated.
+    pub category: Option<String>,
+    /// Filters applied locally to each page.
+    pub filter: ItemFilter,
+    pub format: ListFormat,
+    /// Page the output interactively (terminal only, off with `--no-pager`).
+    pub page: bool,
+}
+
+impl ListOptions {
+    /// Whether `item` passes every filter, including the server-side ones.
+    /// Used where listings are fetched unfiltered, such as recursive walks.
+    pub fn matches(&self, item: &FileItem) -> bool {
+        self.kind.as_ref().is_none_or(|kind| &item.kind == kind)
+            && self.category.as_ref().is_none_or(|list| {
+                item.category
+                    .as_ref()
+                    .is_some_and(|c| list.split(',').any(|wanted| wanted == c))
+            })
+            && self.filter.matches(item)
+    }
+
+    /// The same options without any filter.
+    pub fn unfiltered(&self) -> Self {
+        ListOptions {
+            kind: None,
+            category: None,
+            filter: ItemFilter::default(),
+            ..self.clone()
+        }
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -18,27 +60,49 @@ struct FileListResponse {
     next_marker: Option<String>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug)]
 pub struct FileItem {
     pub name: String,
     pub file_id: String,
     #[serde(rename = "type")]
     pub kind: String,
     pub size: Option<u64>,
-    #[allow(dead_code)]
+    #[serde(default)]
+    pub parent_file_id: String,
+    pub created_at: Option<String>,
     pub updated_at: Option<String>,
+    pub content_hash: Option<String>,
+    pub mime_type: Option<String>,
+    pub category: Option<String>,
+    pub file_extension: Option<String>,
+}
+
+/// How often a throttled or failed listing request is retried, and the
+/// first delay; each retry doubles it unless the server sends `Retry-After`.
+const LIST_RETRIES: u32 = 5;
+const LIST_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Delay requested by a `Retry-After` header given in seconds.
+fn retry_after(res: &Response) -> Option<Duration> {
+    res.headers()
+        .get(header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
 }
 
 /// Issue the OpenAPI request and return the full response body.
 /// 请求阿里云盘 OpenAPI 并返回完整的响应体。
 async fn request_file_list(
+    client: &Client,
     token: &str,
     drive_id: &str,
     parent_file_id: &str,
-    limit: Option<u32>,
+    options: &ListOptions,
     marker: Option<String>,
-    order_by: Option<&str>,
-    order_direction: Option<&str>,
 ) -> Result<FileListResponse> {
     let url = "https://openapi.alipan.com/adrive/v1.0/openFile/list";
 
@@ -53,138 +117,306 @@ async fn request_file_list(
     );
     body.insert(
         "limit".to_string(),
-        serde_json::Value::Number(serde_json::Number::from(limit.unwrap_or(100))),
+        serde_json::Value::Number(serde_json::Number::from(options.limit.unwrap_or(100))),
     );
     body.insert(
         "order_by".to_string(),
-        serde_json::Value::String(order_by.unwrap_or("name_enhanced").to_string()),
+        serde_json::Value::String(
+            options
+                .order_by
+                .as_deref()
+                .unwrap_or("name_enhanced")
+                .to_string(),
+        ),
     );
     body.insert(
         "order_direction".to_string(),
-        serde_json::Value::String(order_direction.unwrap_or("ASC").to_string()),
+        serde_json::Value::String(
+            options
+                .order_direction
+                .as_deref()
+                .unwrap_or("ASC")
+                .to_string(),
+        ),
     );
 
     if let Some(marker) = marker.filter(|m| !m.is_empty()) {
         body.insert("marker".to_string(), serde_json::Value::String(marker));
     }
+    if let Some(kind) = &options.kind {
+        body.insert("type".to_string(), serde_json::Value::String(kind.clone()));
+    }
+    if let Some(category) = &options.category {
+        body.insert(
+            "category".to_string(),
+            serde_json::Value::String(category.clone()),
+        );
+    }
 
-    let client = Client::new();
-    let res = client
-        .post(url)
-        .bearer_auth(token)
-        .json(&body)
-        .send()
-        .await?;
+    // 被限流（429）或服务端错误（5xx）时按指数退避重试，避免大目录遍历因单次失败而中断
+    let mut attempt = 0;
+    let res = loop {
+        let res = client
+            .post(url)
+            .bearer_auth(token)
+            .json(&body)
+            .send()
+            .await?;
+        let status = res.status();
+        let retryable = status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+        if !retryable || attempt == LIST_RETRIES {
+            break res;
+        }
+        let delay = retry_after(&res).unwrap_or(LIST_BACKOFF * 2u32.pow(attempt));
+        attempt += 1;
+        sleep(delay).await;
+    };
 
     if !res.status().is_success() {
         let text = res.text().await?;
         anyhow::bail!("Failed to list files: {}", text);
     }
 
-    Ok(res.json().await?)
+    let mut resp: FileListResponse = res.json().await?;
+    resp.items.retain(|item| options.filter.matches(item));
+    Ok(resp)
+}
+
+/// 按选项拉取一页（`fetch_all` 时为全部）条目，并返回下一页标记。
+/// Fetch one page with matching items, or every page when `fetch_all` is
+/// set, together with the marker of the next page.
+pub async fn fetch_listing(
+    client: &Client,
+    token: &str,
+    drive_id: &str,
+    parent_file_id: &str,
+    options: &ListOptions,
+) -> Result<(Vec<FileItem>, Option<String>)> {
+    let mut items = Vec::new();
+    let mut marker = options.marker.clone();
+    loop {
+        let resp =
+            request_file_list(client, token, drive_id, parent_file_id, options, marker).await?;
+        items.extend(resp.items);
+        let next = resp.next_marker.filter(|m| !m.is_empty());
+        match next {
+            // Pages emptied by the local filters are skipped, as in text mode.
+            Some(next) if options.fetch_all || items.is_empty() => marker = Some(next),
+            next => return Ok((items, next)),
+        }
+    }
 }
 
 /// 获取远程文件列表并打印结果（带中英提示）。
-/// List remote files and print them with bilingual hints.
+/// List remote files and print them with bilingual hints. `folder_path` is
+/// the remote path of the folder, used by the machine-readable formats.
 pub async fn list_remote_files(
     token: &str,
     drive_id: &str,
     parent_file_id: &str,
+    folder_path: &str,
     options: &ListOptions,
 ) -> Result<()> {
-    let mut marker = options.marker.clone();
-    let mut first_page = true;
+    let client = Client::new();
+    if options.format.is_machine() {
+        let (items, next_marker) =
+            fetch_listing(&client, token, drive_id, parent_file_id, options).await?;
+        let listing = Listing {
+            items: items
+                .iter()
+                .map(|item| Record::new(item, folder_path))
+                .collect(),
+            next_marker,
+            total_count: None,
+        };
+        return print_listing(&listing, &options.format);
+    }
 
-    loop {
-        let resp = request_file_list(
-            token,
-            drive_id,
-            parent_file_id,
-            options.limit,
-            marker.clone(),
-            options.order_by.as_deref(),
-            options.order_direction.as_deref(),
-        )
-        .await?;
-
-        if resp.items.is_empty() {
-            if first_page {
-                println!("{}", "(empty)".dimmed());
-            }
-        } else {
-            for item in &resp.items {
-                if item.kind == "folder" {
-                    println!("{}/", item.name.blue());
-                } else {
-                    let size = item.size.unwrap_or(0);
-                    println!("{:<40} {:>10} bytes", item.name, size);
-                }
-            }
-        }
+    let client = &client;
+    pager::show_pages(
+        options.marker.clone(),
+        options.fetch_all,
+        options.page,
+        "(empty)",
+        |marker| async move {
+            let resp =
+                request_file_list(client, token, drive_id, parent_file_id, options, marker).await?;
+            Ok(Page {
+                text: render_items(&resp.items, &options.format),
+                items: resp.items.len(),
+                next: resp.next_marker,
+            })
+        },
+    )
+    .await
+}
 
-        first_page = false;
+/// 递归列出文件夹：与 GNU `ls -R` 一致，每个文件夹先输出路径标题再输出内容。
+/// List a folder and all of its subfolders the way GNU `ls -R` does, one
+/// block per folder headed by its path relative to `label`.
+pub async fn list_recursive(
+    token: &str,
+    drive_id: &str,
+    folder_id: &str,
+    label: &str,
+    options: &ListOptions,
+) -> Result<()> {
+    let listings = walk(token, drive_id, folder_id, options, None, None).await?;
+    let mut out = String::new();
+    render_recursive(&listings, folder_id, label, options, &mut out);
+    if options.page {
+        pager::page(&out)
+    } else {
+        print!("{}", out);
+        Ok(())
+    }
+}
 
-        if options.fetch_all {
-            match resp.next_marker.filter(|m| !m.is_empty()) {
-                Some(next) => {
-                    marker = Some(next);
-                    continue;
-                }
-                None => break,
-            }
-        } else {
-            if let Some(marker) = resp.next_marker.filter(|m| !m.is_empty()) {
-                println!("Next marker: {}", marker.dimmed());
-            }
-            break;
-        }
+fn render_recursive(
+    listings: &Listings,
+    folder_id: &str,
+    label: &str,
+    options: &ListOptions,
+    out: &mut String,
+) {
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(&format!("{}:\n", label.bold()));
+    let items = listings.get(folder_id).map(Vec::as_slice).unwrap_or(&[]);
+    let shown: Vec<FileItem> = items
+        .iter()
+        .filter(|item| options.matches(item))
+        .cloned()
+        .collect();
+    if shown.is_empty() {
+        out.push_str(&format!("{}\n", "(empty)".dimmed()));
+    } else {
+        out.push_str(&render_items(&shown, &options.format));
     }
+    for item in items.iter().filter(|item| item.kind == "folder") {
+        let child = format!("{}/{}", label.trim_end_matches('/'), item.name);
+        render_recursive(listings, &item.file_id, &child, options, out);
+    }
+}
 
-    Ok(())
+/// 将遍历结果按文件夹先后顺序转换为带完整路径的记录。
+/// Append the records of `folder_id` that pass the filters of `options` and,
+/// depth-first, of every subfolder present in `listings`. `dir` is the
+/// remote path of `folder_id`.
+pub fn collect_records<'a>(
+    listings: &'a Listings,
+    folder_id: &str,
+    dir: &str,
+    options: &ListOptions,
+    records: &mut Vec<Record<'a>>,
+) {
+    let Some(items) = listings.get(folder_id) else {
+        return;
+    };
+    for item in items {
+        let record = Record::new(item, dir);
+        let path = record.path.clone();
+        if options.matches(item) {
+            records.push(record);
+        }
+        if item.kind == "folder" {
+            collect_records(listings, &item.file_id, &path, options, records);
+        }
+    }
 }
 
 /// 根据文件夹名查找 file_id。
 /// Find a subfolder id by its name within the given parent.
 pub async fn get_subfolder_id(
+    cache: &ListingCache,
     token: &str,
     drive_id: &str,
     parent_file_id: &str,
     folder_name: &str,
 ) -> Result<Option<String>> {
-    let resp = request_file_list(token, drive_id, parent_file_id, None, None, None, None).await?;
-    for item in resp.items {
-        if item.kind == "folder" && item.name == folder_name {
-            return Ok(Some(item.file_id));
-        }
-    }
+    let item = find_child(cache, token, drive_id, parent_file_id, |item| {
+        item.kind == "folder" && item.name == folder_name
+    })
+    .await?;
+    Ok(item.map(|item| item.file_id))
+}
 
-    Ok(None)
+/// Look up a child in the cached listing, refetching once on a miss in case
+/// the cached listing is stale.
+/// 在缓存的目录列表中查找子项；未命中时强制刷新一次，避免缓存过期导致误判。
+async fn find_child(
+    cache: &ListingCache,
+    token: &str,
+    drive_id: &str,
+    parent_file_id: &str,
+    pred: impl Fn(&FileItem) -> bool,
+) -> Result<Option<FileItem>> {
+    let items = cache.items(token, drive_id, parent_file_id).await?;
+    if let Some(item) = items.iter().find(|item| pred(item)) {
+        return Ok(Some(item.clone()));
+    }
+    let items = cache.refresh(token, drive_id, parent_file_id).await?;
+    Ok(items.iter().find(|item| pred(item)).cloned())
 }
 
-/// 根据文件名查找 file_id。
-/// Find a file id by its display name inside the current directory.
-pub async fn find_file_id_by_name(
+/// 分页拉取文件夹下的全部条目。
+/// Fetch every item of a folder, following `next_marker` until exhausted.
+pub async fn list_all_items(
+    client: &Client,
     token: &str,
     drive_id: &str,
     parent_file_id: &str,
-    filename: &str,
-) -> Result<String> {
-    let resp = request_file_list(token, drive_id, parent_file_id, None, None, None, None).await?;
-    for item in resp.items {
-        if item.name == filename {
-            return Ok(item.file_id);
+) -> Result<Vec<FileItem>> {
+    let options = ListOptions::default();
+    let mut items = Vec::new();
+    let mut marker = None;
+    loop {
+        let resp =
+            request_file_list(client, token, drive_id, parent_file_id, &options, marker).await?;
+        items.extend(resp.items);
+        match resp.next_marker.filter(|m| !m.is_empty()) {
+            Some(next) => marker = Some(next),
+            None => break,
         }
     }
+    Ok(items)
+}
+
+/// 路径不存在（区别于请求失败），调用方可据此只忽略"找不到"的情况。
+/// A path that does not exist, as opposed to a request that failed; lets
+/// callers tell a genuine miss apart from network or auth errors.
+#[derive(Debug)]
+pub struct NotFound(pub String);
+
+impl std::fmt::Display for NotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
 
-    Err(anyhow!(
-        "File '{}' not found in current directory",
-        filename
-    ))
+impl std::error::Error for NotFound {}
+
+/// 根据名称在指定目录中查找文件或文件夹。
+/// Find a file or folder by its display name inside the given folder.
+pub async fn find_item_by_name(
+    cache: &ListingCache,
+    token: &str,
+    drive_id: &str,
+    parent_file_id: &str,
+    name: &str,
+) -> Result<FileItem> {
+    find_child(cache, token, drive_id, parent_file_id, |item| {
+        item.name == name
+    })
+    .await?
+    .ok_or_else(|| NotFound(format!("'{}' not found", name)).into())
 }
 
 /// Resolve a nested path into its final folder id (relative to parent).
 /// 解析相对路径（或空路径）为最终的文件夹 ID。
 pub async fn resolve_path_to_id(
+    cache: &ListingCache,
     token: &str,
     drive_id: &str,
     root_parent_id: &str,
@@ -202,10 +434,10 @@ pub async fn resolve_path_to_id(
         if name == ".." {
             anyhow::bail!("'..' is not supported in resolve_path_to_id");
         }
-        if let Some(id) = get_subfolder_id(token, drive_id, &current_id, name).await? {
+        if let Some(id) = get_subfolder_id(cache, token, drive_id, &current_id, name).await? {
             current_id = id;
         } else {
-            return Err(anyhow!("路径 '{}' 不存在", path));
+            return Err(NotFound(format!("路径 '{}' 不存在", path)).into());
         }
     }
     Ok(current_id)
@@ -1,9 +1,22 @@
+pub mod bandwidth;
+pub mod cache;
+pub mod cat;
+pub mod concurrency;
 pub mod cp;
 pub mod drive;
+pub mod du;
+pub mod filter;
 pub mod get;
+pub mod glob;
 pub mod ls;
 pub mod mkdir;
 pub mod mv;
+pub mod output;
 pub mod put;
+pub mod resume;
 pub mod rm;
 pub mod search;
+pub mod stat;
+pub mod theme;
+pub mod tree;
+pub mod walk;
@@ -0,0 +1,344 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use super::ls::FileItem;
+use super::theme;
+
+/// A column of the long listing format.
+/// 长格式列表中可选的列。
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Column {
+    Type,
+    Size,
+    Created,
+    Updated,
+    Name,
+    FileId,
+    Hash,
+    Mime,
+}
+
+pub const DEFAULT_COLUMNS: &[Column] = &[Column::Type, Column::Size, Column::Updated, Column::Name];
+
+impl Column {
+    pub const NAMES: &'static [&'static str] = &[
+        "type", "size", "created", "updated", "name", "id", "hash", "mime",
+    ];
+
+    pub fn parse(name: &str) -> Result<Self> {
+        Ok(match name {
+            "type" => Column::Type,
+            "size" => Column::Size,
+            "created" => Column::Created,
+            "updated" => Column::Updated,
+            "name" => Column::Name,
+            "id" | "file_id" => Column::FileId,
+            "hash" | "content_hash" => Column::Hash,
+            "mime" | "mime_type" => Column::Mime,
+            other => anyhow::bail!(
+                "Unknown column: {} (allowed: {})",
+                other,
+                Column::NAMES.join(", ")
+            ),
+        })
+    }
+
+    /// Parse a comma-separated column list such as `type,size,name`.
+    pub fn parse_list(list: &str) -> Result<Vec<Self>> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .map(Column::parse)
+            .collect()
+    }
+}
+
+/// How listings are rendered.
+#[derive(Clone, Debug, Default)]
+pub enum ListFormat {
+    /// Name and raw byte size.
+    #[default]
+    Short,
+    /// One aligned row per item with the selected columns.
+    Long { columns: Vec<Column>, human: bool },
+    /// A single JSON document with every record and the pagination marker.
+    Json,
+    /// One JSON record per line, followed by a marker line when paginated.
+    JsonLines,
+    /// CSV with a fixed header row; the marker goes to stderr.
+    Csv,
+}
+
+impl ListFormat {
+    /// Parse `--json`, `--jsonl` or `--csv`.
+    pub fn from_flag(flag: &str) -> Option<Self> {
+        match flag {
+            "--json" => Some(ListFormat::Json),
+            "--jsonl" => Some(ListFormat::JsonLines),
+            "--csv" => Some(ListFormat::Csv),
+            _ => None,
+        }
+    }
+
+    /// Whether the format is meant for scripts rather than people.
+    pub fn is_machine(&self) -> bool {
+        matches!(
+            self,
+            ListFormat::Json | ListFormat::JsonLines | ListFormat::Csv
+        )
+    }
+}
+
+/// Stable machine-readable form of an item. Field names and order are part
+/// of the output contract: add new fields at the end, never rename them.
+/// 条目的机器可读形式。字段名与顺序保持稳定，只允许在末尾追加。
+#[derive(Serialize, Debug)]
+pub struct Record<'a> {
+    pub path: String,
+    pub name: &'a str,
+    #[serde(rename = "type")]
+    pub kind: &'a str,
+    pub size: Option<u64>,
+    pub file_id: &'a str,
+    pub parent_file_id: &'a str,
+    pub created_at: Option<&'a str>,
+    pub updated_at: Option<&'a str>,
+    pub content_hash: Option<&'a str>,
+    pub mime_type: Option<&'a str>,
+    pub category: Option<&'a str>,
+    pub file_extension: Option<&'a str>,
+}
+
+const CSV_HEADER: &str = "path,name,type,size,file_id,parent_file_id,created_at,updated_at,content_hash,mime_type,category,file_extension";
+
+impl<'a> Record<'a> {
+    /// Build the record of `item`, which lives in the remote folder `dir`.
+    /// An empty `dir` means the folder could not be resolved; `path` is then
+    /// left empty as well.
+    pub fn new(item: &'a FileItem, dir: &str) -> Self {
+        Record {
+            path: if dir.is_empty() {
+                String::new()
+            } else {
+                format!("{}/{}", dir.trim_end_matches('/'), item.name)
+            },
+            name: &item.name,
+            kind: &item.kind,
+            size: item.size,
+            file_id: &item.file_id,
+            parent_file_id: &item.parent_file_id,
+            created_at: item.created_at.as_deref(),
+            updated_at: item.updated_at.as_deref(),
+            content_hash: item.content_hash.as_deref(),
+            mime_type: item.mime_type.as_deref(),
+            category: item.category.as_deref(),
+            file_extension: item.file_extension.as_deref(),
+        }
+    }
+
+    fn csv_row(&self) -> String {
+        let size = self.size.map(|s| s.to_string()).unwrap_or_default();
+        [
+            self.path.as_str(),
+            self.name,
+            self.kind,
+            &size,
+            self.file_id,
+            self.parent_file_id,
+            self.created_at.unwrap_or(""),
+            self.updated_at.unwrap_or(""),
+            self.content_hash.unwrap_or(""),
+            self.mime_type.unwrap_or(""),
+            self.category.unwrap_or(""),
+            self.file_extension.unwrap_or(""),
+        ]
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<_>>()
+        .join(",")
+    }
+}
+
+/// A complete listing for the machine-readable formats.
+#[derive(Serialize, Debug)]
+pub struct Listing<'a> {
+    pub items: Vec<Record<'a>>,
+    pub next_marker: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_count: Option<u64>,
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// 以 JSON / JSON Lines / CSV 输出完整列表。
+/// Print a listing in one of the machine-readable formats.
+pub fn print_listing(listing: &Listing, format: &ListFormat) -> Result<()> {
+    match format {
+        ListFormat::Json => println!("{}", serde_json::to_string_pretty(listing)?),
+        ListFormat::JsonLines => {
+            for record in &listing.items {
+                println!("{}", serde_json::to_string(record)?);
+            }
+            if listing.next_marker.is_some() || listing.total_count.is_some() {
+                let mut trailer = serde_json::Map::new();
+                trailer.insert(
+                    "next_marker".to_string(),
+                    listing.next_marker.clone().into(),
+                );
+                if let Some(total) = listing.total_count {
+                    trailer.insert("total_count".to_string(), total.into());
+                }
+                println!("{}", serde_json::Value::Object(trailer));
+            }
+        }
+        ListFormat::Csv => {
+            println!("{}", CSV_HEADER);
+            for record in &listing.items {
+                println!("{}", record.csv_row());
+            }
+            if let Some(total) = listing.total_count {
+                eprintln!("Total count: {}", total);
+            }
+            if let Some(marker) = &listing.next_marker {
+                eprintln!("Next marker: {}", marker);
+            }
+        }
+        ListFormat::Short | ListFormat::Long { .. } => {
+            anyhow::bail!("print_listing only handles machine-readable formats")
+        }
+    }
+    Ok(())
+}
+
+/// 将字节数格式化为易读形式（1024 进制，与 `ls -h` 一致）。
+/// Format a byte count the way `ls -h` does.
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["K", "M", "G", "T", "P", "E"];
+    if bytes < 1024 {
+        return bytes.to_string();
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if value < 10.0 {
+        format!("{:.1}{}", value, UNITS[unit])
+    } else {
+        format!("{:.0}{}", value, UNITS[unit])
+    }
+}
+
+/// 将接口返回的 RFC 3339 时间转换为本地时间显示。
+/// Render an RFC 3339 timestamp from the API in local time.
+pub fn local_time(timestamp: Option<&str>) -> String {
+    timestamp
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn cell(item: &FileItem, column: Column, human: bool) -> String {
+    let is_dir = item.kind == "folder";
+    match column {
+        Column::Type => if is_dir { "d" } else { "-" }.to_string(),
+        Column::Size if is_dir => "-".to_string(),
+        Column::Size => {
+            let size = item.size.unwrap_or(0);
+            if human {
+                human_size(size)
+            } else {
+                size.to_string()
+            }
+        }
+        Column::Created => local_time(item.created_at.as_deref()),
+        Column::Updated => local_time(item.updated_at.as_deref()),
+        Column::Name if is_dir => format!("{}/", item.name),
+        Column::Name => item.name.clone(),
+        Column::FileId => item.file_id.clone(),
+        Column::Hash => item.content_hash.clone().unwrap_or_else(|| "-".to_string()),
+        Column::Mime => item.mime_type.clone().unwrap_or_else(|| "-".to_string()),
+    }
+}
+
+fn render_short(item: &FileItem, out: &mut String) {
+    let name = theme::current().name(item);
+    if item.kind == "folder" {
+        out.push_str(&name);
+    } else {
+        let size = item.size.unwrap_or(0);
+        let pad = 40usize.saturating_sub(item.name.chars().count());
+        out.push_str(&format!("{}{} {:>10} bytes", name, " ".repeat(pad), size));
+    }
+    out.push('\n');
+}
+
+/// 按指定格式打印一组条目（长格式下各列对齐）。
+/// Print a batch of items in the given format, aligning long-format columns.
+pub fn print_items(items: &[FileItem], format: &ListFormat) {
+    print!("{}", render_items(items, format));
+}
+
+/// Render a batch of items the way [`print_items`] prints them.
+pub fn render_items(items: &[FileItem], format: &ListFormat) -> String {
+    let mut out = String::new();
+    let (columns, human) = match format {
+        ListFormat::Long { columns, human } => (columns, *human),
+        _ => {
+            for item in items {
+                render_short(item, &mut out);
+            }
+            return out;
+        }
+    };
+
+    let rows: Vec<Vec<String>> = items
+        .iter()
+        .map(|item| columns.iter().map(|c| cell(item, *c, human)).collect())
+        .collect();
+    let widths: Vec<usize> = (0..columns.len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let theme = theme::current();
+    for (item, row) in items.iter().zip(&rows) {
+        let last = columns.len().saturating_sub(1);
+        let line: Vec<String> = columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                let text = &row[i];
+                let pad = widths[i] - text.chars().count();
+                match column {
+                    Column::Name => {
+                        let name = theme.name(item);
+                        if i == last {
+                            name
+                        } else {
+                            format!("{}{}", name, " ".repeat(pad))
+                        }
+                    }
+                    Column::Size => format!("{}{}", " ".repeat(pad), text),
+                    _ if i == last => text.to_string(),
+                    _ => format!("{}{}", text, " ".repeat(pad)),
+                }
+            })
+            .collect();
+        out.push_str(&line.join("  "));
+        out.push('\n');
+    }
+    out
+}
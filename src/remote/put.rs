@@ -1,5 +1,5 @@
 use anyhow::{anyhow, Result};
-use futures::Stream;
+use futures::stream::{self, Stream};
 use indicatif::{ProgressBar, ProgressStyle};
 use reqwest::{Body, Client};
 use serde_json::{json, Value};
@@ -7,50 +7,43 @@ use std::{
     fs::File,
     io::{Read, Seek, SeekFrom},
     path::Path,
-    pin::Pin,
-    task::{Context, Poll},
+    sync::Arc,
 };
 use tokio::time::{sleep, Duration};
 
+use super::bandwidth::Throttle;
+use super::drive::get_space_info;
+use super::output::human_size;
+
 /// Each part size (500 MB, up to 5 GB allowed by Aliyun)
 const PART_SIZE: usize = 500 * 1024 * 1024;
 
-/// 支持进度追踪的字节流
-struct ProgressStream {
-    data: Vec<u8>,
-    position: usize,
-    progress: ProgressBar,
-    chunk_size: usize,
-}
+/// Bytes handed to the HTTP body at a time.
+const STREAM_PIECE: usize = 8192;
 
-impl ProgressStream {
-    fn new(data: Vec<u8>, progress: ProgressBar) -> Self {
-        Self {
-            data,
-            position: 0,
-            progress,
-            chunk_size: 8192,
-        }
-    }
-}
-
-impl Stream for ProgressStream {
-    type Item = Result<Vec<u8>, std::io::Error>;
-
-    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        if self.position >= self.data.len() {
-            return Poll::Ready(None);
+/// 支持进度追踪与限速的字节流
+/// Stream `data` in small pieces, advancing `progress` and waiting on
+/// `throttle` before each one.
+fn progress_stream(
+    data: Arc<Vec<u8>>,
+    progress: ProgressBar,
+    throttle: Arc<Throttle>,
+) -> impl Stream<Item = Result<Vec<u8>, std::io::Error>> {
+    stream::unfold(0, move |position| {
+        let data = Arc::clone(&data);
+        let progress = progress.clone();
+        let throttle = Arc::clone(&throttle);
+        async move {
+            if position >= data.len() {
+                return None;
+            }
+            let end = (position + STREAM_PIECE).min(data.len());
+            throttle.consume((end - position) as u64).await;
+            // 更新进度条
+            progress.inc((end - position) as u64);
+            Some((Ok(data[position..end].to_vec()), end))
         }
-
-        let end = (self.position + self.chunk_size).min(self.data.len());
-        let chunk = self.data[self.position..end].to_vec();
-        self.position = end;
-
-        // 更新进度条
-        self.progress.inc(chunk.len() as u64);
-
-        Poll::Ready(Some(Ok(chunk)))
-    }
+    })
 }
 
 /// Upload file with real-time progress bar
@@ -59,8 +52,10 @@ pub async fn put_file(
     drive_id: &str,
     parent_file_id: &str,
     local_path: &str,
+    limit_rate: Option<u64>,
 ) -> Result<()> {
     let client = Client::new();
+    let throttle = Throttle::new(limit_rate);
     let path = Path::new(local_path);
     let filename = path
         .file_name()
@@ -77,6 +72,20 @@ pub async fn put_file(
         file_size / 1024 / 1024
     );
 
+    // 0️⃣ 上传前检查剩余空间，避免上传到一半才失败
+    match get_space_info(token).await {
+        Ok(space) if file_size > space.free_size() => {
+            return Err(anyhow!(
+                "Not enough space: {} needs {}, only {} free (see 'df')",
+                filename,
+                human_size(file_size),
+                human_size(space.free_size())
+            ));
+        }
+        Ok(_) => {}
+        Err(err) => eprintln!("⚠️ Could not check remaining space: {}", err),
+    }
+
     let part_count = ((file_size as f64) / (PART_SIZE as f64)).ceil() as usize;
     let part_info_list: Vec<Value> = (1..=part_count)
         .map(|i| json!({ "part_number": i }))
@@ -142,11 +151,12 @@ pub async fn put_file(
         file.seek(SeekFrom::Start(start))?;
         let mut buf = vec![0u8; chunk_size];
         file.read_exact(&mut buf)?;
+        let buf = Arc::new(buf);
 
         let mut retry_count = 0;
         loop {
             // 创建进度追踪流
-            let stream = ProgressStream::new(buf.clone(), pb.clone());
+            let stream = progress_stream(Arc::clone(&buf), pb.clone(), Arc::clone(&throttle));
             let body = Body::wrap_stream(stream);
 
             let put_res = client
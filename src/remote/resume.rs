@@ -0,0 +1,392 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Files are hashed in pieces of this size.
+const READ_BUFFER: usize = 1024 * 1024;
+
+/// 下载中的数据写入 `<文件>.part`，完成后重命名为目标文件。
+/// Data being downloaded goes to `<file>.part` until every chunk is written.
+pub fn part_path(local_path: &Path) -> PathBuf {
+    let mut name = local_path.as_os_str().to_owned();
+    name.push(".part");
+    PathBuf::from(name)
+}
+
+/// Sidecar recording which chunks of the `.part` file are complete.
+pub fn state_path(local_path: &Path) -> PathBuf {
+    let mut name = local_path.as_os_str().to_owned();
+    name.push(".part.json");
+    PathBuf::from(name)
+}
+
+/// 断点续传状态：记录远程文件标识与已完成分片的位图。
+/// Resume state of a partial download: the remote file it belongs to and a
+/// bitmap of the chunks already written to the `.part` file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResumeState {
+    pub file_id: String,
+    pub size: u64,
+    pub content_hash: Option<String>,
+    pub chunk_size: u64,
+    /// Bit `i` of the bitmap is set once chunk `i` is on disk.
+    done: Vec<u64>,
+    /// SHA1 of each chunk as received, empty until the chunk is done.
+    #[serde(default)]
+    chunk_sha1: Vec<String>,
+    /// Changed since the last [`ResumeState::take_dirty`].
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl ResumeState {
+    pub fn new(file_id: &str, size: u64, content_hash: Option<&str>, chunk_size: u64) -> Self {
+        let mut state = Self {
+            file_id: file_id.to_string(),
+            size,
+            content_hash: content_hash.map(str::to_string),
+            chunk_size,
+            done: Vec::new(),
+            chunk_sha1: Vec::new(),
+            dirty: false,
+        };
+        state.done = vec![0; state.chunk_count().div_ceil(64)];
+        state.chunk_sha1 = vec![String::new(); state.chunk_count()];
+        state
+    }
+
+    /// Load the state saved next to `local_path`, if it describes the same
+    /// remote file and its `.part` file is still there.
+    pub fn load_matching(
+        local_path: &Path,
+        file_id: &str,
+        size: u64,
+        content_hash: Option<&str>,
+    ) -> Option<Self> {
+        let text = fs::read_to_string(state_path(local_path)).ok()?;
+        let mut state: Self = serde_json::from_str(&text).ok()?;
+        let part_len = fs::metadata(part_path(local_path)).ok()?.len();
+        let same = state.file_id == file_id
+            && state.size == size
+            && state.content_hash.as_deref() == content_hash
+            && state.chunk_size > 0
+            && state.done.len() == state.chunk_count().div_ceil(64)
+            && part_len == size;
+        if !same {
+            return None;
+        }
+        // States written before chunk hashes were recorded have none.
+        state.chunk_sha1.resize(state.chunk_count(), String::new());
+        Some(state)
+    }
+
+    /// Whether any resume data (state or `.part` file) exists for `local_path`.
+    pub fn exists(local_path: &Path) -> bool {
+        state_path(local_path).exists() || part_path(local_path).exists()
+    }
+
+    /// Delete the `.part` file and its state.
+    pub fn discard(local_path: &Path) {
+        let _ = fs::remove_file(part_path(local_path));
+        let _ = fs::remove_file(state_path(local_path));
+    }
+
+    /// Write the state atomically (temporary file, then rename).
+    pub fn save(&self, local_path: &Path) -> Result<()> {
+        let path = state_path(local_path);
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+        fs::write(&tmp, serde_json::to_vec(self)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.size.div_ceil(self.chunk_size) as usize
+    }
+
+    /// Inclusive byte range of chunk `index`.
+    pub fn chunk_range(&self, index: usize) -> (u64, u64) {
+        let start = index as u64 * self.chunk_size;
+        let end = (start + self.chunk_size).min(self.size) - 1;
+        (start, end)
+    }
+
+    pub fn is_done(&self, index: usize) -> bool {
+        self.done[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// Record chunk `index` as written, with the SHA1 of the bytes received.
+    pub fn mark_done(&mut self, index: usize, sha1: String) {
+        self.done[index / 64] |= 1 << (index % 64);
+        self.chunk_sha1[index] = sha1;
+        self.dirty = true;
+    }
+
+    /// Forget chunk `index` so that it is fetched again.
+    pub fn unmark(&mut self, index: usize) {
+        self.done[index / 64] &= !(1 << (index % 64));
+        self.chunk_sha1[index].clear();
+        self.dirty = true;
+    }
+
+    /// A copy to save if anything changed since the last call, so the
+    /// caller can write it without holding a lock on `self`.
+    pub fn take_dirty(&mut self) -> Option<Self> {
+        if !self.dirty {
+            return None;
+        }
+        self.dirty = false;
+        Some(self.clone())
+    }
+
+    /// Indices of the chunks still to download.
+    pub fn missing(&self) -> Vec<usize> {
+        (0..self.chunk_count())
+            .filter(|i| !self.is_done(*i))
+            .collect()
+    }
+
+    /// Bytes already written by completed chunks.
+    pub fn completed_bytes(&self) -> u64 {
+        (0..self.chunk_count())
+            .filter(|i| self.is_done(*i))
+            .map(|i| {
+                let (start, end) = self.chunk_range(i);
+                end - start + 1
+            })
+            .sum()
+    }
+}
+
+/// 小写十六进制的 SHA1 摘要。
+/// Lower-case hex digest of an incremental SHA1.
+pub fn sha1_hex(hasher: Sha1) -> String {
+    hex(&hasher.finalize())
+}
+
+/// 流式计算本地文件的 SHA1。
+/// Lower-case hex SHA1 of a local file, read in pieces.
+pub fn sha1_file(path: &Path) -> Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha1::new();
+    let mut buffer = vec![0; READ_BUFFER];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex(&hasher.finalize()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 校验已完成的 `.part` 文件：大小、每个分片的 SHA1 以及整个文件的 `content_hash`。
+/// 返回需要重新下载的分片；整体哈希不符但无法定位到分片时返回全部分片。
+/// Check a complete `.part` file against the remote size, the SHA1 recorded
+/// for each chunk and the file's `content_hash`. Returns the chunks to fetch
+/// again: those that changed on disk, or every chunk when only the
+/// whole-file hash disagrees and the bad range cannot be narrowed down.
+pub fn verify_part(
+    local_path: &Path,
+    state: &ResumeState,
+    content_hash: Option<&str>,
+) -> Result<Vec<usize>> {
+    let part = part_path(local_path);
+    let len = fs::metadata(&part)?.len();
+    if len != state.size {
+        return Err(anyhow!(
+            "Size mismatch: expected {} bytes, found {}",
+            state.size,
+            len
+        ));
+    }
+
+    let mut file = File::open(&part)?;
+    let mut whole = Sha1::new();
+    let mut buffer = vec![0; READ_BUFFER.min(state.size as usize)];
+    let mut bad = Vec::new();
+    for index in 0..state.chunk_count() {
+        let (start, end) = state.chunk_range(index);
+        let mut chunk = Sha1::new();
+        let mut left = end - start + 1;
+        while left > 0 {
+            let piece = &mut buffer[..left.min(READ_BUFFER as u64) as usize];
+            file.read_exact(piece)?;
+            whole.update(&*piece);
+            chunk.update(&*piece);
+            left -= piece.len() as u64;
+        }
+        let recorded = &state.chunk_sha1[index];
+        if !recorded.is_empty() && *recorded != sha1_hex(chunk) {
+            bad.push(index);
+        }
+    }
+    if !bad.is_empty() {
+        return Ok(bad);
+    }
+    match content_hash {
+        Some(expected) if !expected.eq_ignore_ascii_case(&hex(&whole.finalize())) => {
+            Ok((0..state.chunk_count()).collect())
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory under the system temp dir, removed on drop.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "shellaliyun-resume-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn sha1_of(bytes: &[u8]) -> String {
+        let mut hasher = Sha1::new();
+        hasher.update(bytes);
+        sha1_hex(hasher)
+    }
+
+    #[test]
+    fn last_chunk_is_partial() {
+        let mut state = ResumeState::new("id", 10, None, 4);
+        assert_eq!(state.chunk_count(), 3);
+        assert_eq!(state.chunk_range(0), (0, 3));
+        assert_eq!(state.chunk_range(1), (4, 7));
+        assert_eq!(state.chunk_range(2), (8, 9));
+        state.mark_done(2, String::new());
+        assert_eq!(state.completed_bytes(), 2);
+        assert_eq!(state.missing(), [0, 1]);
+    }
+
+    #[test]
+    fn exact_multiple_of_chunk_size() {
+        let state = ResumeState::new("id", 8, None, 4);
+        assert_eq!(state.chunk_count(), 2);
+        assert_eq!(state.chunk_range(1), (4, 7));
+    }
+
+    #[test]
+    fn empty_file() {
+        let state = ResumeState::new("id", 0, None, 4);
+        assert_eq!(state.chunk_count(), 0);
+        assert!(state.missing().is_empty());
+        assert_eq!(state.completed_bytes(), 0);
+    }
+
+    #[test]
+    fn bitmap_spans_several_words() {
+        let mut state = ResumeState::new("id", 130, None, 1);
+        assert_eq!(state.chunk_count(), 130);
+        for index in [0, 63, 64, 129] {
+            state.mark_done(index, String::new());
+        }
+        assert!(state.is_done(63) && state.is_done(64) && state.is_done(129));
+        assert!(!state.is_done(1) && !state.is_done(65) && !state.is_done(128));
+        assert_eq!(state.completed_bytes(), 4);
+        assert_eq!(state.missing().len(), 126);
+
+        state.unmark(64);
+        assert!(!state.is_done(64) && state.is_done(63));
+        assert_eq!(state.completed_bytes(), 3);
+        assert!(state.missing().contains(&64));
+    }
+
+    #[test]
+    fn dirty_flag() {
+        let mut state = ResumeState::new("id", 10, None, 4);
+        assert!(state.take_dirty().is_none());
+        state.mark_done(1, String::new());
+        assert!(state.take_dirty().unwrap().is_done(1));
+        assert!(state.take_dirty().is_none());
+        state.unmark(1);
+        assert!(state.take_dirty().is_some());
+    }
+
+    #[test]
+    fn load_matching_checks_the_remote_file() {
+        let dir = TempDir::new("load");
+        let local = dir.0.join("file.bin");
+        let mut state = ResumeState::new("id", 10, Some("HASH"), 4);
+        state.mark_done(1, sha1_of(b"4567"));
+        state.save(&local).unwrap();
+
+        // No `.part` file yet.
+        assert!(ResumeState::load_matching(&local, "id", 10, Some("HASH")).is_none());
+        fs::write(part_path(&local), [0; 10]).unwrap();
+
+        let loaded = ResumeState::load_matching(&local, "id", 10, Some("HASH")).unwrap();
+        assert_eq!(loaded.missing(), [0, 2]);
+        assert_eq!(loaded.chunk_sha1[1], sha1_of(b"4567"));
+
+        assert!(ResumeState::load_matching(&local, "other", 10, Some("HASH")).is_none());
+        assert!(ResumeState::load_matching(&local, "id", 11, Some("HASH")).is_none());
+        assert!(ResumeState::load_matching(&local, "id", 10, Some("OTHER")).is_none());
+        assert!(ResumeState::load_matching(&local, "id", 10, None).is_none());
+
+        fs::write(part_path(&local), [0; 9]).unwrap();
+        assert!(ResumeState::load_matching(&local, "id", 10, Some("HASH")).is_none());
+
+        fs::write(part_path(&local), [0; 10]).unwrap();
+        let mut saved = serde_json::to_value(&state).unwrap();
+        saved["chunk_size"] = 0.into();
+        fs::write(state_path(&local), saved.to_string()).unwrap();
+        assert!(ResumeState::load_matching(&local, "id", 10, Some("HASH")).is_none());
+
+        ResumeState::discard(&local);
+        assert!(!ResumeState::exists(&local));
+    }
+
+    #[test]
+    fn verify_part_finds_changed_chunks() {
+        let dir = TempDir::new("verify");
+        let local = dir.0.join("file.bin");
+        let data = b"0123456789";
+        let mut state = ResumeState::new("id", 10, None, 4);
+        for index in 0..state.chunk_count() {
+            let (start, end) = state.chunk_range(index);
+            state.mark_done(index, sha1_of(&data[start as usize..=end as usize]));
+        }
+
+        fs::write(part_path(&local), data).unwrap();
+        let whole = sha1_of(data);
+        assert!(verify_part(&local, &state, Some(&whole))
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            verify_part(&local, &state, Some("0000")).unwrap(),
+            [0, 1, 2]
+        );
+
+        fs::write(part_path(&local), b"0123x56789").unwrap();
+        assert_eq!(verify_part(&local, &state, Some(&whole)).unwrap(), [1]);
+
+        fs::write(part_path(&local), b"012345678").unwrap();
+        assert!(verify_part(&local, &state, None).is_err());
+    }
+}
@@ -1,16 +1,10 @@
-use crate::remote::ls::find_file_id_by_name;
 use anyhow::Result;
 use reqwest::Client;
 use serde_json::json;
 
-pub async fn remove_file(
-    token: &str,
-    drive_id: &str,
-    parent_file_id: &str,
-    filename: &str,
-) -> Result<()> {
-    let file_id = find_file_id_by_name(token, drive_id, parent_file_id, filename).await?;
-
+/// 删除指定 file_id 的文件或文件夹（`filename` 仅用于提示）。
+/// Delete a file or folder by id; `filename` is only used for messages.
+pub async fn remove_file(token: &str, drive_id: &str, file_id: &str, filename: &str) -> Result<()> {
     let url = "https://openapi.alipan.com/adrive/v1.0/openFile/delete";
     let body = json!({
         "drive_id": drive_id,
@@ -1,9 +1,14 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+
 use anyhow::Result;
-use colored::Colorize;
 use reqwest::Client;
 use serde::Deserialize;
 
 use super::ls::FileItem;
+use super::output::{print_listing, render_items, ListFormat, Listing, Record};
+use super::stat::get_file_detail;
+use crate::pager::{self, Page};
 
 #[derive(Clone, Debug, Default)]
 pub struct SearchOptions {
@@ -13,6 +18,9 @@ pub struct SearchOptions {
     pub order_direction: Option<String>,
     pub return_total_count: bool,
     pub fetch_all: bool,
+    pub format: ListFormat,
+    /// Page the output interactively (terminal only, off with `--no-pager`).
+    pub page: bool,
 }
 
 #[derive(Deserialize, Debug)]
@@ -83,50 +91,114 @@ pub async fn search_files(
     query: &str,
     options: &SearchOptions,
 ) -> Result<()> {
-    let mut marker = options.marker.clone();
-    let mut first_page = true;
-
-    loop {
-        let resp = request_search(token, drive_id, query, options, marker.clone()).await?;
+    if options.format.is_machine() {
+        return print_search_listing(token, drive_id, query, options).await;
+    }
 
-        if resp.items.is_empty() {
-            if first_page {
-                println!("{}", "(no results)".dimmed());
-            }
-        } else {
-            for item in &resp.items {
-                if item.kind == "folder" {
-                    println!("{}/", item.name.blue());
-                } else {
-                    let size = item.size.unwrap_or(0);
-                    println!("{:<40} {:>10} bytes", item.name, size);
+    let first_page = Cell::new(true);
+    let first_page = &first_page;
+    pager::show_pages(
+        options.marker.clone(),
+        options.fetch_all,
+        options.page,
+        "(no results)",
+        |marker| async move {
+            let resp = request_search(token, drive_id, query, options, marker).await?;
+            let mut text = render_items(&resp.items, &options.format);
+            if first_page.replace(false) && options.return_total_count {
+                if let Some(total) = resp.total_count {
+                    text.push_str(&format!("Total count: {}\n", total));
                 }
             }
+            Ok(Page {
+                text,
+                items: resp.items.len(),
+                next: resp.next_marker,
+            })
+        },
+    )
+    .await
+}
+
+/// 以机器可读格式输出搜索结果；每个结果的完整路径通过其父目录链解析。
+/// Emit search results in a machine-readable format, resolving each
+/// result's full path from its parent chain.
+async fn print_search_listing(
+    token: &str,
+    drive_id: &str,
+    query: &str,
+    options: &SearchOptions,
+) -> Result<()> {
+    let mut items = Vec::new();
+    let mut marker = options.marker.clone();
+    let mut total_count = None;
+    let next_marker = loop {
+        let resp = request_search(token, drive_id, query, options, marker).await?;
+        if total_count.is_none() && options.return_total_count {
+            total_count = resp.total_count;
         }
+        items.extend(resp.items);
+        match resp.next_marker.filter(|m| !m.is_empty()) {
+            Some(next) if options.fetch_all => marker = Some(next),
+            next => break next,
+        }
+    };
+
+    let mut folder_paths: HashMap<String, String> = HashMap::new();
+    folder_paths.insert("root".to_string(), "/root".to_string());
+    for item in &items {
+        resolve_folder_path(token, drive_id, &item.parent_file_id, &mut folder_paths).await;
+    }
 
-        if first_page && options.return_total_count {
-            if let Some(total) = resp.total_count {
-                println!("Total count: {}", total);
+    let listing = Listing {
+        items: items
+            .iter()
+            .map(|item| Record::new(item, &folder_paths[&item.parent_file_id]))
+            .collect(),
+        next_marker,
+        total_count,
+    };
+    print_listing(&listing, &options.format)
+}
+
+/// 解析目录的完整路径；沿父目录链向上查找，途经的每个目录都写入缓存，
+/// 查询失败时该链上的目录记为空路径。
+/// Resolve a folder's full path by walking up its parent chain, caching every
+/// folder seen on the way; folders whose chain cannot be resolved map to an
+/// empty path.
+async fn resolve_folder_path(
+    token: &str,
+    drive_id: &str,
+    folder_id: &str,
+    cache: &mut HashMap<String, String>,
+) {
+    let mut chain: Vec<(String, String)> = Vec::new();
+    let mut current = folder_id.to_string();
+    let base = loop {
+        if let Some(path) = cache.get(&current) {
+            break Some(path.clone());
+        }
+        match get_file_detail(token, drive_id, &current).await {
+            Ok(detail) => {
+                chain.push((current, detail.name));
+                current = detail.parent_file_id;
             }
+            Err(_) => break None,
         }
+    };
 
-        first_page = false;
-
-        if options.fetch_all {
-            match resp.next_marker.filter(|m| !m.is_empty()) {
-                Some(next) => {
-                    marker = Some(next);
-                    continue;
-                }
-                None => break,
+    match base {
+        Some(mut path) if !path.is_empty() => {
+            for (id, name) in chain.into_iter().rev() {
+                path = format!("{}/{}", path.trim_end_matches('/'), name);
+                cache.insert(id, path.clone());
             }
-        } else {
-            if let Some(next) = resp.next_marker.filter(|m| !m.is_empty()) {
-                println!("Next marker: {}", next.dimmed());
+        }
+        _ => {
+            for (id, _) in chain {
+                cache.insert(id, String::new());
             }
-            break;
+            cache.insert(current, String::new());
         }
     }
-
-    Ok(())
 }
@@ -0,0 +1,202 @@
+use anyhow::Result;
+use colored::Colorize;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use super::output::{human_size, local_time};
+
+/// File metadata returned by `openFile/get`.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct FileDetail {
+    pub file_id: String,
+    pub parent_file_id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub size: Option<u64>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub content_hash: Option<String>,
+    pub content_hash_name: Option<String>,
+    pub mime_type: Option<String>,
+    pub file_extension: Option<String>,
+    pub category: Option<String>,
+    #[serde(default)]
+    pub starred: bool,
+    pub thumbnail: Option<String>,
+    pub video_media_metadata: Option<Map<String, Value>>,
+    pub image_media_metadata: Option<Map<String, Value>>,
+}
+
+/// 获取单个文件/文件夹的详细信息。
+/// Fetch the metadata of a single file or folder by id.
+pub async fn get_file_detail(token: &str, drive_id: &str, file_id: &str) -> Result<FileDetail> {
+    let url = "https://openapi.alipan.com/adrive/v1.0/openFile/get";
+    let body = json!({
+        "drive_id": drive_id,
+        "file_id": file_id
+    });
+
+    let client = Client::new();
+    let res = client
+        .post(url)
+        .bearer_auth(token)
+        .json(&body)
+        .send()
+        .await?;
+
+    if !res.status().is_success() {
+        let text = res.text().await?;
+        anyhow::bail!("Failed to get file info: {}", text);
+    }
+
+    Ok(res.json().await?)
+}
+
+/// Rebuild the `(path, file_id)` stack from root down to the given folder by
+/// following `parent_file_id` links, so renamed folders still resolve.
+/// 沿 parent_file_id 逐级向上，重建从根目录到目标文件夹的 (路径, ID) 栈。
+pub async fn get_folder_stack(
+    token: &str,
+    drive_id: &str,
+    folder_id: &str,
+) -> Result<Vec<(String, String)>> {
+    let mut chain = Vec::new();
+    let mut current = folder_id.to_string();
+    while current != "root" {
+        let detail = get_file_detail(token, drive_id, &current).await?;
+        if chain.is_empty() && detail.kind != "folder" {
+            anyhow::bail!("'{}' is not a folder", detail.name);
+        }
+        current = detail.parent_file_id.clone();
+        chain.push(detail);
+    }
+
+    let mut stack = vec![("/root".to_string(), "root".to_string())];
+    for detail in chain.into_iter().rev() {
+        let path = format!("{}/{}", stack.last().unwrap().0, detail.name);
+        stack.push((path, detail.file_id));
+    }
+    Ok(stack)
+}
+
+/// 以 JSON 输出文件详情（附加完整路径）；多个目标时输出为一个 JSON 数组。
+/// Print file metadata as JSON with each full remote path added: a single
+/// object for one target, an array when there are several.
+pub fn print_details_json(details: &[(FileDetail, String)]) -> Result<()> {
+    let mut values = Vec::new();
+    for (detail, path) in details {
+        let mut value = serde_json::to_value(detail)?;
+        if let Value::Object(map) = &mut value {
+            map.insert("path".to_string(), Value::String(path.clone()));
+        }
+        values.push(value);
+    }
+    let document = if values.len() == 1 {
+        values.swap_remove(0)
+    } else {
+        Value::Array(values)
+    };
+    println!("{}", serde_json::to_string_pretty(&document)?);
+    Ok(())
+}
+
+/// Render a JSON scalar without quotes.
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Width × height from a media metadata object.
+fn resolution(meta: &Map<String, Value>) -> Option<String> {
+    let width = meta.get("width").and_then(scalar)?;
+    let height = meta.get("height").and_then(scalar)?;
+    Some(format!("{}x{}", width, height))
+}
+
+/// Capture time of a photo: the `time` field, or `DateTimeOriginal` from the
+/// EXIF blob, which the API returns as a JSON-encoded string.
+fn exif_time(meta: &Map<String, Value>) -> Option<String> {
+    if let Some(time) = meta.get("time").and_then(scalar) {
+        return Some(time);
+    }
+    let exif: Value = serde_json::from_str(meta.get("exif")?.as_str()?).ok()?;
+    let original = exif.get("DateTimeOriginal")?;
+    original
+        .get("value")
+        .and_then(scalar)
+        .or_else(|| scalar(original))
+}
+
+/// Format a duration in seconds (the API sends it as a string) as `h:mm:ss`.
+fn duration(meta: &Map<String, Value>) -> Option<String> {
+    let seconds: f64 = meta.get("duration").and_then(scalar)?.parse().ok()?;
+    let total = seconds.round() as u64;
+    Some(format!(
+        "{}:{:02}:{:02}",
+        total / 3600,
+        total / 60 % 60,
+        total % 60
+    ))
+}
+
+/// 以易读形式打印文件详情；不存在的字段不显示。
+/// Print a file's metadata, one field per line, skipping absent fields.
+pub fn print_detail(detail: &FileDetail, path: &str) {
+    let mut rows: Vec<(&str, String)> = vec![
+        ("Path", path.to_string()),
+        ("ID", detail.file_id.clone()),
+        ("Parent ID", detail.parent_file_id.clone()),
+        ("Type", detail.kind.clone()),
+    ];
+    if let Some(size) = detail.size.filter(|_| detail.kind != "folder") {
+        rows.push(("Size", format!("{} bytes ({})", size, human_size(size))));
+    }
+    rows.push(("Created", local_time(detail.created_at.as_deref())));
+    rows.push(("Updated", local_time(detail.updated_at.as_deref())));
+    if let Some(hash) = &detail.content_hash {
+        let algorithm = detail.content_hash_name.as_deref().unwrap_or("unknown");
+        rows.push(("Content hash", format!("{} ({})", hash, algorithm)));
+    }
+    let optional = [
+        ("MIME type", &detail.mime_type),
+        ("Extension", &detail.file_extension),
+        ("Category", &detail.category),
+    ];
+    for (label, value) in optional {
+        if let Some(value) = value {
+            rows.push((label, value.clone()));
+        }
+    }
+    rows.push((
+        "Starred",
+        if detail.starred { "yes" } else { "no" }.to_string(),
+    ));
+    if let Some(thumbnail) = &detail.thumbnail {
+        rows.push(("Thumbnail", thumbnail.clone()));
+    }
+    if let Some(video) = &detail.video_media_metadata {
+        if let Some(duration) = duration(video) {
+            rows.push(("Duration", duration));
+        }
+        if let Some(resolution) = resolution(video) {
+            rows.push(("Resolution", resolution));
+        }
+    }
+    if let Some(image) = &detail.image_media_metadata {
+        if let Some(resolution) = resolution(image) {
+            rows.push(("Resolution", resolution));
+        }
+        if let Some(time) = exif_time(image) {
+            rows.push(("EXIF time", time));
+        }
+    }
+
+    for (label, value) in rows {
+        println!("{}: {}", format!("{:>13}", label).bold(), value);
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use super::ls::FileItem;
+
+/// Built-in colours, in `LS_COLORS` syntax. Besides the GNU keys `di`, `fi`
+/// and `*.ext`, the theme understands the drive's file categories.
+const DEFAULT_COLORS: &str = "di=34:video=35:image=95:audio=36:zip=31:doc=33";
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Colours and indicators for remote listings.
+/// 远程列表的配色与类型标识。
+#[derive(Clone, Debug, Default)]
+pub struct Theme {
+    /// SGR codes keyed by `di`, `fi`, a category name or `*.ext`.
+    codes: HashMap<String, String>,
+    /// Prefix each name with an icon for its type.
+    pub classify: bool,
+}
+
+impl Theme {
+    /// Build the theme from the built-in defaults, then `LS_COLORS`, then the
+    /// config file's `colors`, then `SHELLALIYUN_COLORS`; later entries win.
+    pub fn load(config_colors: Option<&str>, classify: bool) -> Self {
+        let mut theme = Theme {
+            codes: HashMap::new(),
+            classify,
+        };
+        theme.merge(DEFAULT_COLORS);
+        let ls_colors = std::env::var("LS_COLORS").ok();
+        let own = std::env::var("SHELLALIYUN_COLORS").ok();
+        for spec in [ls_colors.as_deref(), config_colors, own.as_deref()]
+            .into_iter()
+            .flatten()
+        {
+            theme.merge(spec);
+        }
+        theme
+    }
+
+    /// Merge `key=codes` entries separated by `:`. Extension keys are matched
+    /// case-insensitively; malformed entries are ignored, as GNU ls does.
+    fn merge(&mut self, spec: &str) {
+        for entry in spec.split(':') {
+            let Some((key, code)) = entry.split_once('=') else {
+                continue;
+            };
+            if key.is_empty() || !code.chars().all(|c| c.is_ascii_digit() || c == ';') {
+                continue;
+            }
+            let key = match key.strip_prefix("*.") {
+                Some(ext) => format!("*.{}", ext.to_lowercase()),
+                None => key.to_string(),
+            };
+            self.codes.insert(key, code.to_string());
+        }
+    }
+
+    fn code_for(&self, item: &FileItem) -> Option<&str> {
+        if item.kind == "folder" {
+            return self.codes.get("di").map(String::as_str);
+        }
+        let ext = item
+            .file_extension
+            .clone()
+            .or_else(|| item.name.rsplit_once('.').map(|(_, ext)| ext.to_string()));
+        ext.and_then(|ext| self.codes.get(&format!("*.{}", ext.to_lowercase())))
+            .or_else(|| item.category.as_ref().and_then(|c| self.codes.get(c)))
+            .or_else(|| self.codes.get("fi"))
+            .map(String::as_str)
+    }
+
+    /// Colour `text` (usually the item's name) for `item`. Plain text is
+    /// returned when colours are off (`NO_COLOR`, or output is not a TTY).
+    pub fn paint(&self, item: &FileItem, text: &str) -> String {
+        match self.code_for(item) {
+            Some(code)
+                if !code.is_empty() && colored::control::SHOULD_COLORIZE.should_colorize() =>
+            {
+                format!("\x1b[{}m{}\x1b[0m", code, text)
+            }
+            _ => text.to_string(),
+        }
+    }
+
+    /// Icon marking the type of `item`, followed by a space, when enabled.
+    pub fn indicator(&self, item: &FileItem) -> &'static str {
+        if !self.classify {
+            return "";
+        }
+        if item.kind == "folder" {
+            return "📁 ";
+        }
+        match item.category.as_deref() {
+            Some("video") => "🎬 ",
+            Some("image") => "📷 ",
+            Some("audio") => "🎵 ",
+            Some("zip") => "📦 ",
+            Some("doc") => "📄 ",
+            _ => "📎 ",
+        }
+    }
+
+    /// Indicator, coloured name and the trailing `/` of folders.
+    pub fn name(&self, item: &FileItem) -> String {
+        let slash = if item.kind == "folder" { "/" } else { "" };
+        format!(
+            "{}{}{}",
+            self.indicator(item),
+            self.paint(item, &item.name),
+            slash
+        )
+    }
+}
+
+/// Install the theme used by every listing. Later calls are ignored.
+pub fn init(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+/// The installed theme, or the built-in one if none was installed.
+pub fn current() -> &'static Theme {
+    THEME.get_or_init(|| Theme::load(None, false))
+}
@@ -0,0 +1,110 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use super::ls::{FileItem, ListOptions};
+use super::output::human_size;
+use super::theme;
+use super::walk::{walk, Listings};
+use crate::pager;
+
+#[derive(Clone, Debug, Default)]
+pub struct TreeOptions {
+    /// Deepest level to descend into (`-L`); unlimited when `None`.
+    pub max_depth: Option<usize>,
+    /// Show folders only (`-d`).
+    pub dirs_only: bool,
+    /// Page the output interactively (terminal only, off with `--no-pager`).
+    pub page: bool,
+}
+
+#[derive(Default)]
+struct Totals {
+    folders: u64,
+    files: u64,
+    bytes: u64,
+}
+
+/// 以树形结构打印文件夹，末尾汇总文件夹数、文件数与总大小。
+/// Print the folder hierarchy under `root_id` as a tree, followed by the
+/// number of folders, files and bytes shown.
+pub async fn print_tree(
+    token: &str,
+    drive_id: &str,
+    root_id: &str,
+    root_label: &str,
+    options: &TreeOptions,
+) -> Result<()> {
+    let listings = walk(
+        token,
+        drive_id,
+        root_id,
+        &ListOptions::default(),
+        options.max_depth,
+        None,
+    )
+    .await?;
+
+    let mut out = format!("{}\n", root_label.blue().bold());
+    let mut totals = Totals::default();
+    render_children(&listings, root_id, "", options, &mut totals, &mut out);
+
+    out.push('\n');
+    if options.dirs_only {
+        out.push_str(&format!("{} directories\n", totals.folders));
+    } else {
+        out.push_str(&format!(
+            "{} directories, {} files, {} bytes ({})\n",
+            totals.folders,
+            totals.files,
+            totals.bytes,
+            human_size(totals.bytes)
+        ));
+    }
+
+    if options.page {
+        pager::page(&out)
+    } else {
+        print!("{}", out);
+        Ok(())
+    }
+}
+
+fn render_children(
+    listings: &Listings,
+    folder_id: &str,
+    prefix: &str,
+    options: &TreeOptions,
+    totals: &mut Totals,
+    out: &mut String,
+) {
+    let Some(items) = listings.get(folder_id) else {
+        return;
+    };
+    let theme = theme::current();
+    let shown: Vec<&FileItem> = items
+        .iter()
+        .filter(|item| !options.dirs_only || item.kind == "folder")
+        .collect();
+
+    for (i, item) in shown.iter().enumerate() {
+        let last = i + 1 == shown.len();
+        let branch = if last { "└── " } else { "├── " };
+        if item.kind == "folder" {
+            totals.folders += 1;
+            out.push_str(&format!("{}{}{}\n", prefix, branch, theme.name(item)));
+            let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+            render_children(listings, &item.file_id, &child_prefix, options, totals, out);
+        } else {
+            let size = item.size.unwrap_or(0);
+            totals.files += 1;
+            totals.bytes += size;
+            out.push_str(&format!(
+                "{}{}{} {}\n",
+                prefix,
+                branch,
+                theme.name(item),
+                format!("[{}]", human_size(size)).dimmed()
+            ));
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use anyhow::Result;
+use futures::stream::{FuturesUnordered, StreamExt};
+use indicatif::ProgressBar;
+use reqwest::Client;
+use std::collections::{HashMap, VecDeque};
+
+use super::ls::{fetch_listing, FileItem, ListOptions};
+
+/// How many folder listings a walk requests at the same time.
+pub const WALK_CONCURRENCY: usize = 4;
+
+/// Full listings of every folder reached by [`walk`], keyed by folder id.
+pub type Listings = HashMap<String, Vec<FileItem>>;
+
+/// 递归遍历文件夹：并发（有上限）拉取各层目录，每个目录完整翻页。
+/// Walk the subtree under `root_id`, listing up to [`WALK_CONCURRENCY`]
+/// folders at once and paginating each one fully. `max_depth` limits how
+/// many levels are listed (1 lists only the root). Items keep the order
+/// requested by `options`; its filters are ignored. `progress`, when given, advances once per folder.
+/// Throttled (429) or failed (5xx) listings are retried with backoff, so
+/// hitting the API rate limit slows a large walk down instead of ending it.
+pub async fn walk(
+    token: &str,
+    drive_id: &str,
+    root_id: &str,
+    options: &ListOptions,
+    max_depth: Option<usize>,
+    progress: Option<&ProgressBar>,
+) -> Result<Listings> {
+    let client = Client::new();
+    // Filters would hide the folders the walk needs to descend into, so
+    // listings are fetched whole and callers filter what they display.
+    let options = ListOptions {
+        marker: None,
+        fetch_all: true,
+        ..options.unfiltered()
+    };
+    let mut listings = Listings::new();
+    if max_depth == Some(0) {
+        return Ok(listings);
+    }
+
+    let mut pending = VecDeque::from([(root_id.to_string(), 1)]);
+    let mut in_flight = FuturesUnordered::new();
+    loop {
+        while in_flight.len() < WALK_CONCURRENCY {
+            let Some((folder_id, depth)) = pending.pop_front() else {
+                break;
+            };
+            in_flight.push(list_folder(
+                &client, token, drive_id, &options, folder_id, depth,
+            ));
+        }
+
+        let Some(result) = in_flight.next().await else {
+            break;
+        };
+        let (folder_id, depth, items) = result?;
+        if max_depth.is_none_or(|max| depth < max) {
+            pending.extend(
+                items
+                    .iter()
+                    .filter(|item| item.kind == "folder")
+                    .map(|item| (item.file_id.clone(), depth + 1)),
+            );
+        }
+        if let Some(pb) = progress {
+            pb.inc(1);
+        }
+        listings.insert(folder_id, items);
+    }
+    Ok(listings)
+}
+
+async fn list_folder(
+    client: &Client,
+    token: &str,
+    drive_id: &str,
+    options: &ListOptions,
+    folder_id: String,
+    depth: usize,
+) -> Result<(String, usize, Vec<FileItem>)> {
+    let (items, _) = fetch_listing(client, token, drive_id, &folder_id, options).await?;
+    Ok((folder_id, depth, items))
+}